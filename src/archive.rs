@@ -0,0 +1,118 @@
+use {
+    flate2::{write::GzEncoder, Compression},
+    std::{
+        fs::{self, File},
+        io::{self, Write},
+        path::{Path, PathBuf},
+    },
+    zip::{write::FileOptions, CompressionMethod, ZipWriter},
+};
+
+// Most people zip the image folder right after downloading it so they can
+// share it, so the program can write the files to an archive as they're
+// saved, either as a `.zip`, which every OS can open, or as a `.tar.gz`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Kind {
+    Zip,
+    TarGz,
+}
+
+impl Kind {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Kind::Zip => "zip",
+            Kind::TarGz => "tar.gz",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Options {
+    pub kind: Kind,
+    // If this isn't set, the files are removed from the image directory once
+    // they've been archived and posted, so only the archive is left
+    pub keep_folder: bool,
+}
+
+enum Inner {
+    Zip(ZipWriter<File>),
+    TarGz(tar::Builder<GzEncoder<File>>),
+}
+
+pub struct Writer {
+    inner: Inner,
+    path: PathBuf,
+    // The files that were added, so that they can be removed from the folder
+    // if the user only wants the archive
+    added: Vec<PathBuf>,
+}
+
+impl Writer {
+    // The archive is created next to the image directory and named after it,
+    // so `./Discord Images` becomes `./Discord Images.zip`
+    pub fn create(dir: &Path, kind: Kind) -> io::Result<Writer> {
+        let name = dir
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| String::from("Discord Images"));
+
+        let path = dir.with_file_name(format!("{}.{}", name, kind.extension()));
+        let file = File::create(&path)?;
+
+        let inner = match kind {
+            Kind::Zip => Inner::Zip(ZipWriter::new(file)),
+            Kind::TarGz => Inner::TarGz(tar::Builder::new(GzEncoder::new(
+                file,
+                Compression::default(),
+            ))),
+        };
+
+        Ok(Writer {
+            inner,
+            path,
+            added: Vec::new(),
+        })
+    }
+
+    // Files are stored under `name`, which can include folders such as
+    // `thumbs/`, so the archive has the same layout as the image directory
+    pub fn add(&mut self, file: &Path, name: &str) -> io::Result<()> {
+        match &mut self.inner {
+            Inner::Zip(zip) => {
+                // Images are already compressed, so deflating them again
+                // would only waste time, while text files shrink a lot
+                let method = match file.extension().and_then(|ext| ext.to_str()) {
+                    Some("html") | Some("json") | Some("csv") | Some("txt") => {
+                        CompressionMethod::Deflated
+                    }
+                    _ => CompressionMethod::Stored,
+                };
+
+                zip.start_file(name, FileOptions::default().compression_method(method))?;
+                zip.write_all(&fs::read(file)?)?;
+            }
+            Inner::TarGz(tar) => tar.append_path_with_name(file, name)?,
+        }
+
+        self.added.push(file.to_path_buf());
+
+        Ok(())
+    }
+
+    // The archive has to be finished for its index (or the end of the gzip
+    // stream) to be written, otherwise it can't be opened. The archived files
+    // are returned along with its path, as they can't be removed until
+    // they're no longer needed, such as for uploading the collage.
+    pub fn finish(self) -> io::Result<(PathBuf, Vec<PathBuf>)> {
+        match self.inner {
+            Inner::Zip(mut zip) => {
+                zip.finish()?;
+            }
+            Inner::TarGz(tar) => {
+                tar.into_inner()?.finish()?;
+            }
+        }
+
+        Ok((self.path, self.added))
+    }
+}
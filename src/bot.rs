@@ -0,0 +1,324 @@
+use {
+    crate::{
+        config::{snowflake, Config, Order},
+        downloader::{DownloadReport, Downloader},
+        gateway,
+    },
+    chrono::{Duration, NaiveDate, TimeZone, Utc},
+    serenity::{
+        async_trait,
+        client::{bridge::gateway::GatewayIntents, Context, EventHandler},
+        model::{
+            channel::Channel,
+            gateway::Ready,
+            id::ChannelId,
+            interactions::{
+                ApplicationCommand, ApplicationCommandOptionType, Interaction,
+                InteractionResponseType, InteractionType,
+            },
+        },
+    },
+    std::path::Path,
+};
+
+// Instead of asking for settings in the terminal, this mode turns the program
+// into a bot with an `/archive` command, so that people who have never opened
+// a terminal can start downloads from inside Discord, such as by typing
+// `/archive channel:#success since:3d`
+pub async fn all(selected: Config) {
+    println!();
+
+    // `/archive` is registered once the bot is connected, in `ready()`
+    let intents = GatewayIntents::non_privileged();
+
+    if let Err(err) = gateway::connect(&selected.clone(), Handler { selected }, intents).await {
+        println!("\nLost connection to Discord: {}", err);
+    }
+}
+
+struct Handler {
+    selected: Config,
+}
+
+#[async_trait]
+impl EventHandler for Handler {
+    async fn ready(&self, ctx: Context, ready: Ready) {
+        // Registering the command every time the bot starts is the simplest
+        // way of keeping it up to date, as Discord simply overwrites it
+        let registered = ApplicationCommand::create_global_application_command(&ctx.http, |cmd| {
+            cmd.name("archive")
+                .description("Download the images posted in a channel")
+                .create_option(|opt| {
+                    opt.name("channel")
+                        .description("The channel to download, defaults to the one selected when the bot started")
+                        .kind(ApplicationCommandOptionType::Channel)
+                        .required(false)
+                })
+                .create_option(|opt| {
+                    opt.name("since")
+                        .description("How far back to search, such as `3d`, `12h`, `2w` or `DD/MM/YY`")
+                        .kind(ApplicationCommandOptionType::String)
+                        .required(false)
+                })
+                .create_option(|opt| {
+                    opt.name("limit")
+                        .description("The maximum number of images to download, starting from the newest")
+                        .kind(ApplicationCommandOptionType::Integer)
+                        .required(false)
+                })
+        })
+        .await;
+
+        match registered {
+            Ok(_) => {
+                println!("Connected as {}!", ready.user.name);
+                println!("Type `/archive` in Discord to download a channel, close the program to stop.\n");
+            }
+            Err(err) => println!("Failed to register the `/archive` command: {}", err),
+        }
+    }
+
+    async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
+        let data = match (&interaction.kind, &interaction.data) {
+            (InteractionType::ApplicationCommand, Some(data)) if data.name == "archive" => data,
+            _ => return,
+        };
+
+        // Since archives are saved on the machine running the bot, only staff
+        // members who can manage messages are allowed to start them
+        let allowed = interaction
+            .member
+            .as_ref()
+            .and_then(|member| member.permissions)
+            .is_some_and(|permissions| permissions.manage_messages());
+
+        if !allowed {
+            reply(
+                &ctx,
+                &interaction,
+                "You need the `Manage Messages` permission to archive channels!",
+            )
+            .await;
+            return;
+        }
+
+        let mut selected = self.selected.clone();
+        let mut picked = false;
+
+        // Each option is looked up by name, as Discord only sends the ones
+        // the user actually filled in
+        for option in data.options.iter() {
+            let value = match &option.value {
+                Some(value) => value,
+                None => continue,
+            };
+
+            match option.name.as_str() {
+                "channel" => {
+                    if let Some(channel) = value.as_str() {
+                        // The member's permissions above are the ones in the
+                        // channel the command was used in, so they're checked
+                        // again in the one being archived, where Discord sends
+                        // them along with the channel, to keep staff from
+                        // archiving channels they can't read themselves
+                        let readable = channel
+                            .parse::<u64>()
+                            .ok()
+                            .and_then(|id| data.resolved.channels.get(&ChannelId(id)))
+                            .and_then(|channel| channel.permissions)
+                            .is_some_and(|permissions| {
+                                permissions.read_messages() && permissions.read_message_history()
+                            });
+
+                        if !readable {
+                            reply(
+                                &ctx,
+                                &interaction,
+                                &format!("You can't read <#{}>, so you can't archive it!", channel),
+                            )
+                            .await;
+                            return;
+                        }
+
+                        selected.channel = channel.to_string();
+                        picked = true;
+                    }
+                }
+                "since" => match value.as_str().and_then(since) {
+                    Some(date) => selected.date = date,
+                    None => {
+                        reply(&ctx, &interaction, "Invalid `since` option! Use a duration such as `3d`, `12h` or `2w`, or a date as `DD/MM/YY`.").await;
+                        return;
+                    }
+                },
+                // Asking for a number of images usually means the latest ones.
+                // Discord lets people type any integer, but `0` would mean no
+                // limit at all, so only positive ones are accepted.
+                "limit" => match value.as_u64().filter(|limit| *limit > 0) {
+                    Some(limit) => {
+                        selected.quantity = limit.min(u32::MAX as u64) as u32;
+                        selected.order = Order::Newest;
+                    }
+                    None => {
+                        reply(
+                            &ctx,
+                            &interaction,
+                            "Invalid `limit` option! Use a number of images above 0.",
+                        )
+                        .await;
+                        return;
+                    }
+                },
+                _ => {}
+            }
+        }
+
+        // Without the `channel` option, the one selected when the bot was
+        // started is archived, which is only allowed from inside its own
+        // server, as staff from the other servers the bot is in have no say
+        // over it
+        if !picked {
+            let guild = match ctx
+                .http
+                .get_channel(selected.channel.parse().unwrap_or(0))
+                .await
+            {
+                Ok(Channel::Guild(channel)) => Some(channel.guild_id),
+                _ => None,
+            };
+
+            if guild.is_none() || guild != interaction.guild_id {
+                reply(
+                    &ctx,
+                    &interaction,
+                    "The default channel is in another server, so pick one with the `channel` option!",
+                )
+                .await;
+                return;
+            }
+        }
+
+        // Each channel gets its own folder, so that collages made for
+        // different channels don't overwrite each other
+        let path = Path::new(&selected.path).join(&selected.channel);
+
+        if let Err(err) = std::fs::create_dir_all(&path) {
+            reply(
+                &ctx,
+                &interaction,
+                &format!("Failed to create the image folder: {}", err),
+            )
+            .await;
+            return;
+        }
+
+        selected.path = path.to_string_lossy().to_string();
+
+        // Discord only waits three seconds for a reply, so the bot says it's
+        // thinking and edits its response once the download is over
+        if let Err(err) = interaction
+            .create_interaction_response(&ctx.http, |res| {
+                res.kind(InteractionResponseType::DeferredChannelMessageWithSource)
+            })
+            .await
+        {
+            println!("Failed to reply to `/archive`: {}", err);
+            return;
+        }
+
+        println!("Archiving <#{}>...", selected.channel);
+
+        // If the download couldn't be done at all, such as when the bot can't
+        // see the channel, the reason is sent instead of the results
+        let content = match Downloader::from(selected.clone()).run().await {
+            Ok(report) => describe(&report, &selected.path),
+            Err(err) => format!("Failed to archive <#{}>: {}", selected.channel, err),
+        };
+
+        if let Err(err) = interaction
+            .edit_original_interaction_response(&ctx.http, |res| res.content(content))
+            .await
+        {
+            println!("Failed to reply to `/archive`: {}", err);
+        }
+    }
+}
+
+fn describe(report: &DownloadReport, path: &str) -> String {
+    // Problems with single images are only printed, as they'd clutter the
+    // reply
+    for err in &report.errors {
+        println!("{}", err);
+    }
+
+    let mut content = report.describe();
+
+    if !report.saved.is_empty() {
+        content.push_str(&format!("\nThe images were saved in `{}`.", path));
+    }
+
+    if let Some(archive) = &report.archive {
+        content.push_str(&format!(
+            "\nThe archive was saved as `{}`.",
+            archive.display()
+        ));
+    }
+
+    if let Some(collage) = &report.collage {
+        content.push_str(&format!(
+            "\nThe collage was saved as `{}`.",
+            collage.display()
+        ));
+    }
+
+    content
+}
+
+// Errors are sent as the command's response, instead of being printed on the
+// machine running the bot where nobody would see them
+async fn reply(ctx: &Context, interaction: &Interaction, content: &str) {
+    if let Err(err) = interaction
+        .create_interaction_response(&ctx.http, |res| {
+            res.kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|msg| msg.content(content))
+        })
+        .await
+    {
+        println!("Failed to reply to `/archive`: {}", err);
+    }
+}
+
+// The `since` option accepts either a duration, counting back from now, or a
+// date in the same `DD/MM/YY` format as the terminal prompt, and is converted
+// to a Snowflake just like the Start Date
+fn since(input: &str) -> Option<u64> {
+    let input = input.trim().to_lowercase();
+
+    for format in ["%d/%m/%y", "%d/%m/%Y"].iter() {
+        if let Ok(date) = NaiveDate::parse_from_str(&input, format) {
+            return Some(snowflake(Utc.from_utc_datetime(&date.and_hms(0, 0, 0))));
+        }
+    }
+
+    let unit = input.chars().last()?;
+    let amount: i64 = input[..input.len() - unit.len_utf8()].parse().ok()?;
+
+    let seconds = match unit {
+        'm' => 60,
+        'h' => 60 * 60,
+        'd' => 24 * 60 * 60,
+        'w' => 7 * 24 * 60 * 60,
+        _ => return None,
+    };
+
+    // `chrono` panics on durations and dates it can't represent, so huge
+    // amounts are checked here and rejected like any other invalid input
+    let millis = amount
+        .checked_mul(seconds)
+        .and_then(|seconds| seconds.checked_mul(1000))
+        .filter(|millis| *millis >= 0)?;
+
+    Some(snowflake(
+        Utc::now().checked_sub_signed(Duration::milliseconds(millis))?,
+    ))
+}
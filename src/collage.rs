@@ -0,0 +1,313 @@
+use {
+    crate::{format::Format, metadata},
+    image::{
+        error::{LimitError, LimitErrorKind},
+        imageops::{self, FilterType},
+        DynamicImage, ImageError, ImageResult, Rgb, RgbImage,
+    },
+    std::path::{Path, PathBuf},
+};
+
+// Grids give every image a cell of the same size, while masonry layouts keep
+// each image's aspect ratio and stack them in columns, like Pinterest does
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Layout {
+    Grid,
+    Masonry,
+}
+
+// This only applies to grids, since masonry layouts never crop their images.
+// `Contain` shrinks the image until it fits the cell, leaving some background
+// visible, while `Cover` fills the whole cell and crops whatever sticks out.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Fit {
+    Contain,
+    Cover,
+}
+
+// Just like in the `Config` `struct`, zero is used for settings that the
+// program should figure out by itself, which in this case are the canvas's
+// height and the number of columns
+#[derive(Clone, Debug)]
+pub struct Options {
+    pub layout: Layout,
+    pub width: u32,
+    pub height: u32,
+    pub columns: u32,
+    pub gutter: u32,
+    pub background: Rgb<u8>,
+    pub fit: Fit,
+    pub format: Format,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        // A 4K square is big enough to be posted on Twitter without losing too
+        // much quality, which is where most collages end up
+        Options {
+            layout: Layout::Grid,
+            width: 4096,
+            height: 0,
+            columns: 0,
+            gutter: 8,
+            background: Rgb([255, 255, 255]),
+            fit: Fit::Cover,
+            format: Format::Png,
+        }
+    }
+}
+
+// Colors are typed as hex codes, such as `#1A1A1A`, with the hash being
+// optional since people tend to forget it
+pub fn parse_color(input: &str) -> Option<Rgb<u8>> {
+    let hex = input.trim().trim_start_matches('#');
+
+    if hex.len() != 6 || !hex.is_ascii() {
+        return None;
+    }
+
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+
+    Some(Rgb([channel(0)?, channel(2)?, channel(4)?]))
+}
+
+// This renders the collage and saves it in the selected format, returning the
+// path it was saved to so that it can be shown to the user
+pub fn save(images: &[PathBuf], options: &Options, dir: &Path) -> ImageResult<PathBuf> {
+    let path = dir.join(format!("collage.{}", options.format.extension()));
+
+    options
+        .format
+        .write(&DynamicImage::ImageRgb8(render(images, options)?), &path)?;
+
+    Ok(path)
+}
+
+// JPEGs can't be taller or wider than this, and a canvas as big as the limit
+// on both sides would already take a few gigabytes of memory, so collages are
+// also limited to as many pixels as a 16K square
+const MAX_EDGE: u32 = 65535;
+const MAX_PIXELS: u64 = 16384 * 16384;
+
+// A canvas that's too big is refused before it's allocated, as the program
+// would otherwise crash while trying to make it
+pub fn render(images: &[PathBuf], options: &Options) -> ImageResult<RgbImage> {
+    // Only the headers are read at first, so that thousands of full-size
+    // images don't have to be kept in memory at the same time, and files that
+    // aren't images the `image` crate understands are skipped
+    //
+    // Photos taken sideways are turned upright using their EXIF orientation,
+    // where the values from `5` to `8` swap their width and height
+    let images: Vec<(&PathBuf, (u32, u32))> = images
+        .iter()
+        .filter_map(|path| {
+            image::image_dimensions(path)
+                .ok()
+                .filter(|(w, h)| *w > 0 && *h > 0)
+                .map(|(w, h)| match metadata::orientation(path) {
+                    Some(5..=8) => (path, (h, w)),
+                    _ => (path, (w, h)),
+                })
+        })
+        .collect();
+
+    let cells = match options.layout {
+        Layout::Grid => grid(&images, options),
+        Layout::Masonry => masonry(&images, options),
+    };
+
+    // If the height is automatic, the canvas is made as tall as the content,
+    // plus the gutter at the bottom
+    let height = if options.height > 0 {
+        options.height
+    } else {
+        cells
+            .iter()
+            .map(|cell| cell.y + cell.height)
+            .max()
+            .unwrap_or(0)
+            + options.gutter
+    };
+
+    let height = height.max(1);
+
+    if options.width > MAX_EDGE
+        || height > MAX_EDGE
+        || options.width as u64 * height as u64 > MAX_PIXELS
+    {
+        return Err(ImageError::Limits(LimitError::from_kind(
+            LimitErrorKind::DimensionError,
+        )));
+    }
+
+    let mut canvas = RgbImage::from_pixel(options.width, height, options.background);
+
+    for (cell, (path, _)) in cells.iter().zip(images.iter()) {
+        // Images that fail to decode are left blank instead of ruining the
+        // whole collage, as the header might have been fine while the rest of
+        // the file is corrupted
+        let img = match image::open(path) {
+            Ok(img) => metadata::upright(img, metadata::orientation(path)),
+            Err(_) => continue,
+        };
+
+        let img = match (options.layout, options.fit) {
+            (Layout::Grid, Fit::Cover) => {
+                img.resize_to_fill(cell.width, cell.height, FilterType::CatmullRom)
+            }
+            _ => img.resize(cell.width, cell.height, FilterType::CatmullRom),
+        }
+        .to_rgb8();
+
+        // `resize()` keeps the aspect ratio, so the image is centered in its
+        // cell in case it came out smaller than it
+        let x = cell.x + (cell.width - img.width().min(cell.width)) / 2;
+        let y = cell.y + (cell.height - img.height().min(cell.height)) / 2;
+
+        imageops::overlay(&mut canvas, &img, x, y);
+    }
+
+    Ok(canvas)
+}
+
+// The position and size of each image on the canvas
+struct Cell {
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+}
+
+fn grid(images: &[(&PathBuf, (u32, u32))], options: &Options) -> Vec<Cell> {
+    let count = images.len() as u32;
+
+    if count == 0 {
+        return Vec::new();
+    }
+
+    // If the number of columns wasn't selected, it's picked so that the cells
+    // are roughly square, which is how most collages look
+    let columns = if options.columns > 0 {
+        options.columns.min(count)
+    } else if options.height > 0 {
+        let ratio = options.width as f64 / options.height as f64;
+        ((count as f64 * ratio).sqrt().ceil() as u32).clamp(1, count)
+    } else {
+        (count as f64).sqrt().ceil() as u32
+    };
+
+    let rows = count.div_ceil(columns);
+
+    let width = cell_size(options.width, columns, options.gutter);
+    let height = if options.height > 0 {
+        cell_size(options.height, rows, options.gutter)
+    } else {
+        width
+    };
+
+    (0..count)
+        .map(|i| Cell {
+            x: options.gutter + (i % columns) * (width + options.gutter),
+            y: options.gutter + (i / columns) * (height + options.gutter),
+            width,
+            height,
+        })
+        .collect()
+}
+
+fn masonry(images: &[(&PathBuf, (u32, u32))], options: &Options) -> Vec<Cell> {
+    if images.is_empty() {
+        return Vec::new();
+    }
+
+    // Masonry columns look best when they're narrow, so at least four are
+    // used by default, unless there are fewer images than that. With lots of
+    // images, more columns are added, just like in grids, so that the canvas
+    // ends up roughly square instead of hundreds of images tall: each column
+    // is `1 / columns` of the width, so the images' total height, relative to
+    // their width, has to be split over `columns²` to fill one width.
+    let columns = if options.columns > 0 {
+        options.columns
+    } else {
+        let ratios: f64 = images.iter().map(|(_, (w, h))| *h as f64 / *w as f64).sum();
+
+        (ratios.sqrt().ceil() as u32).max(4)
+    }
+    .min(images.len() as u32);
+
+    let width = cell_size(options.width, columns, options.gutter);
+
+    // Each image is scaled to the column's width and added to the shortest
+    // column, which keeps the columns at similar heights
+    let layout = |width: u32| {
+        let mut heights = vec![options.gutter; columns as usize];
+
+        images
+            .iter()
+            .map(|(_, (w, h))| {
+                let column = (0..columns as usize)
+                    .min_by_key(|&c| heights[c])
+                    .unwrap_or(0);
+
+                let height = ((*h as f64 * width as f64 / *w as f64).round() as u32).max(1);
+
+                let cell = Cell {
+                    x: options.gutter + column as u32 * (width + options.gutter),
+                    y: heights[column],
+                    width,
+                    height,
+                };
+
+                heights[column] += height + options.gutter;
+
+                cell
+            })
+            .collect::<Vec<Cell>>()
+    };
+
+    let fits = |cells: &[Cell]| {
+        options.height == 0
+            || cells
+                .iter()
+                .map(|cell| cell.y + cell.height)
+                .max()
+                .unwrap_or(0)
+                + options.gutter
+                <= options.height
+    };
+
+    let mut cells = layout(width);
+
+    // If the canvas has a fixed height and the content doesn't fit, the
+    // widest columns that do fit are found with a binary search, and are then
+    // centered horizontally
+    if !fits(&cells) {
+        let (mut low, mut high) = (1, width);
+
+        while low < high {
+            let mid = (low + high).div_ceil(2);
+
+            if fits(&layout(mid)) {
+                low = mid;
+            } else {
+                high = mid - 1;
+            }
+        }
+
+        cells = layout(low);
+
+        let used = columns * low + (columns + 1) * options.gutter;
+        let offset = options.width.saturating_sub(used) / 2;
+
+        for cell in cells.iter_mut() {
+            cell.x += offset;
+        }
+    }
+
+    cells
+}
+
+// The space left after removing the gutters is split evenly between the cells
+fn cell_size(total: u32, cells: u32, gutter: u32) -> u32 {
+    (total.saturating_sub(gutter * (cells + 1)) / cells.max(1)).max(1)
+}
@@ -0,0 +1,102 @@
+use {
+    crate::{format::Format, metadata},
+    image::{codecs::gif::GifDecoder, io::Reader, AnimationDecoder, ImageFormat, ImageResult},
+    std::{
+        fs::{create_dir_all, remove_file, rename, File},
+        io::BufReader,
+        path::{Path, PathBuf},
+    },
+};
+
+// When originals are kept, they're moved to this folder so that the image
+// directory only contains files in the selected format
+pub const FOLDER: &str = "originals";
+
+// Animated GIFs can't be stored as PNGs or JPEGs, so the user gets to choose
+// between leaving them alone and only keeping their first frame
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Gif {
+    Keep,
+    Flatten,
+}
+
+#[derive(Clone, Debug)]
+pub struct Options {
+    pub format: Format,
+    pub keep_original: bool,
+    pub gif: Gif,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            format: Format::Png,
+            keep_original: false,
+            gif: Gif::Keep,
+        }
+    }
+}
+
+// This re-encodes a saved image in the selected format, returning the path of
+// the file that should be used from now on, which is the original one if it
+// was left untouched
+pub fn normalize(path: &Path, options: &Options) -> ImageResult<PathBuf> {
+    // The format is guessed from the file's contents rather than from its
+    // extension, since Discord doesn't check that they match
+    let reader = Reader::open(path)?.with_guessed_format()?;
+    let source = reader.format();
+
+    // Images that are already in the right format don't need to be touched,
+    // as re-encoding a JPEG would only lose quality
+    let already = matches!(
+        (source, options.format),
+        (Some(ImageFormat::Png), Format::Png) | (Some(ImageFormat::Jpeg), Format::Jpeg(_))
+    );
+
+    if already || (source == Some(ImageFormat::Gif) && options.gif == Gif::Keep && animated(path)) {
+        return Ok(path.to_path_buf());
+    }
+
+    // Decoding a GIF as a regular image only returns its first frame, which is
+    // exactly what flattening means. The new file won't have an orientation
+    // tag, so photos taken sideways are turned upright first.
+    let img = metadata::upright(reader.decode()?, metadata::orientation(path));
+
+    let converted = path.with_extension(options.format.extension());
+
+    // If the original has the same name as the converted file (for example a
+    // WebP image that was saved with a `.png` extension), it's moved out of
+    // the way first so it isn't overwritten while it's being read
+    let original = if options.keep_original {
+        let dir = path
+            .parent()
+            .map(|dir| dir.join(FOLDER))
+            .unwrap_or_else(|| PathBuf::from(FOLDER));
+        create_dir_all(&dir)?;
+
+        let moved = dir.join(path.file_name().unwrap_or_default());
+        rename(path, &moved)?;
+
+        Some(moved)
+    } else {
+        None
+    };
+
+    options.format.write(&img, &converted)?;
+
+    if original.is_none() && converted != path {
+        remove_file(path)?;
+    }
+
+    Ok(converted)
+}
+
+// A GIF is only considered animated if it has more than one frame, as plenty
+// of static images are saved as GIFs too
+fn animated(path: &Path) -> bool {
+    File::open(path)
+        .ok()
+        .and_then(|file| GifDecoder::new(BufReader::new(file)).ok())
+        .map(|decoder| decoder.into_frames().take(2).count() > 1)
+        .unwrap_or(false)
+}
@@ -0,0 +1,280 @@
+use {
+    crate::{
+        archive, collage,
+        config::{snowflake, Config, Mode, Order},
+        convert,
+        endpoint::Endpoints,
+        error::Result,
+        filter::Filter,
+        http,
+        listing::Match,
+        post,
+        progress::Event,
+        run, thumbnail,
+    },
+    chrono::{DateTime, Utc},
+    std::path::{Path, PathBuf},
+};
+
+// The terminal prompts are handy for people running the program by itself, but
+// tools that embed it need to set everything from code, so this builder makes
+// the same `Config` the prompts would, starting from the Default Settings, and
+// runs the download without ever reading from stdin or printing to stdout
+pub struct Downloader {
+    config: Config,
+    progress: Option<Box<dyn Fn(Event) + Send + Sync>>,
+}
+
+impl Downloader {
+    pub fn new(token: &str, channel: &str) -> Downloader {
+        Downloader {
+            progress: None,
+            config: Config {
+                token: token.to_string(),
+                channel: channel.to_string(),
+                channels: Vec::new(),
+                mode: Mode::Download,
+                date: 0,
+                until: 0,
+                around: 0,
+                quantity: 0,
+                per_message: 0,
+                per_author: 0,
+                order: Order::Oldest,
+                path: String::from("Discord Images"),
+                filter: Filter::default(),
+                convert: None,
+                strip_metadata: false,
+                thumbnails: None,
+                collage: None,
+                gallery: false,
+                archive: None,
+                post: None,
+                dry_run: false,
+                export: None,
+                endpoints: Endpoints::default(),
+                client: http::client(&http::Options::default()).unwrap_or_default(),
+            },
+        }
+    }
+
+    // Only messages sent after this date are searched, just like the Start
+    // Date prompt
+    pub fn since(mut self, date: DateTime<Utc>) -> Downloader {
+        self.config.date = snowflake(date);
+        self
+    }
+
+    // Messages can be used as anchors too, downloading the images sent after
+    // or before one, or in the page of messages around it
+    pub fn after(mut self, message: u64) -> Downloader {
+        self.config.date = message;
+        self
+    }
+
+    pub fn before(mut self, message: u64) -> Downloader {
+        self.config.until = message;
+        self
+    }
+
+    pub fn around(mut self, message: u64) -> Downloader {
+        self.config.around = message;
+        self
+    }
+
+    // Limits count images, and `0` means that there isn't one
+    pub fn limit(mut self, quantity: u32) -> Downloader {
+        self.config.quantity = quantity;
+        self
+    }
+
+    pub fn per_message(mut self, limit: u32) -> Downloader {
+        self.config.per_message = limit;
+        self
+    }
+
+    pub fn per_author(mut self, limit: u32) -> Downloader {
+        self.config.per_author = limit;
+        self
+    }
+
+    // With `Order::Newest`, the download starts from the most recent message
+    // and goes back to the Start Date, so a limit keeps the latest images
+    // instead, while `Order::Random(seed)` picks a sample as big as the limit
+    // from the whole range and `Order::Reactions(emoji)` picks the most
+    // reacted images in it
+    pub fn order(mut self, order: Order) -> Downloader {
+        self.config.order = order;
+        self
+    }
+
+    // The folder is created when the download starts if it doesn't exist
+    pub fn path<P: AsRef<Path>>(mut self, path: P) -> Downloader {
+        self.config.path = path.as_ref().to_string_lossy().to_string();
+        self
+    }
+
+    pub fn filter(mut self, filter: Filter) -> Downloader {
+        self.config.filter = filter;
+        self
+    }
+
+    pub fn convert(mut self, options: convert::Options) -> Downloader {
+        self.config.convert = Some(options);
+        self
+    }
+
+    pub fn strip_metadata(mut self, strip: bool) -> Downloader {
+        self.config.strip_metadata = strip;
+        self
+    }
+
+    pub fn thumbnails(mut self, options: thumbnail::Options) -> Downloader {
+        self.config.thumbnails = Some(options);
+        self
+    }
+
+    pub fn collage(mut self, options: collage::Options) -> Downloader {
+        self.config.collage = Some(options);
+        self
+    }
+
+    // Only the images in pinned messages are downloaded
+    pub fn pins(mut self, pins: bool) -> Downloader {
+        self.config.mode = if pins { Mode::Pins } else { Mode::Download };
+        self
+    }
+
+    // The gallery is saved as `index.html` in the image directory
+    pub fn gallery(mut self, gallery: bool) -> Downloader {
+        self.config.gallery = gallery;
+        self
+    }
+
+    pub fn archive(mut self, options: archive::Options) -> Downloader {
+        self.config.archive = Some(options);
+        self
+    }
+
+    pub fn post(mut self, options: post::Options) -> Downloader {
+        self.config.post = Some(options);
+        self
+    }
+
+    // A dry run lists the matching images in the report's `matched` without
+    // downloading them
+    pub fn dry_run(mut self, dry_run: bool) -> Downloader {
+        self.config.dry_run = dry_run;
+        self
+    }
+
+    // The list of matching images is written to this file, as JSON if it ends
+    // in `.json` and as CSV otherwise
+    pub fn export<P: AsRef<Path>>(mut self, path: P) -> Downloader {
+        self.config.export = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    // Discord's own API and CDN are used unless other ones are given, such as
+    // `Endpoints::from_env()`, which is what the CLI uses
+    pub fn endpoints(mut self, endpoints: Endpoints) -> Downloader {
+        self.config.endpoints = endpoints;
+        self
+    }
+
+    // Requests are sent through a default client unless one is given, such
+    // as one made by `http::client()` to use a proxy
+    pub fn client(mut self, client: reqwest::Client) -> Downloader {
+        self.config.client = client;
+        self
+    }
+
+    // The callback receives an `Event` for every page and attachment, such as
+    // to show a progress bar, and is called from the task running the download
+    pub fn on_progress<F>(mut self, callback: F) -> Downloader
+    where
+        F: Fn(Event) + Send + Sync + 'static,
+    {
+        self.progress = Some(Box::new(callback));
+        self
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    pub async fn run(&self) -> Result<DownloadReport> {
+        run::download(&self.config, &|event| {
+            if let Some(callback) = &self.progress {
+                callback(event);
+            }
+        })
+        .await
+    }
+}
+
+// A `Config` made by the prompts can also be used, which is how the CLI and
+// the bot run their downloads
+impl From<Config> for Downloader {
+    fn from(config: Config) -> Downloader {
+        Downloader {
+            config,
+            progress: None,
+        }
+    }
+}
+
+// Everything that happened during a download is returned instead of being
+// printed, so that the caller can decide how to show it
+pub struct DownloadReport {
+    pub channel: String,
+    // Every image that passed the filters, which is all that's filled in
+    // during a dry run
+    pub matched: Vec<Match>,
+    // The final paths of the saved images, after they were converted
+    pub saved: Vec<PathBuf>,
+    // Images that contained location data before it was removed
+    pub located: Vec<PathBuf>,
+    pub collage: Option<PathBuf>,
+    pub gallery: Option<PathBuf>,
+    pub archive: Option<PathBuf>,
+    // This is only set if the results were meant to be posted
+    pub posted: Option<Result<()>>,
+    // Failures that didn't stop the download, such as an image that couldn't
+    // be converted, which are still worth telling the user about. Errors that
+    // did stop it are returned by `Downloader::run()` as an `Error` instead.
+    pub errors: Vec<String>,
+}
+
+impl DownloadReport {
+    // The total size of the matching images, in bytes
+    pub fn bytes(&self) -> u64 {
+        self.matched.iter().map(|m| m.size).sum()
+    }
+
+    // This is the message sent on Discord, both by the bot and when the
+    // results are posted to a channel, with the channel being mentioned so
+    // that it can be clicked on
+    pub fn describe(&self) -> String {
+        let mut content = if self.saved.is_empty() {
+            format!("<#{}> doesn't contain any matching images!", self.channel)
+        } else {
+            format!(
+                "Saved {} image{} from <#{}>!",
+                self.saved.len(),
+                if self.saved.len() == 1 { "" } else { "s" },
+                self.channel
+            )
+        };
+
+        if !self.located.is_empty() {
+            content.push_str(&format!(
+                "\nRemoved location data from {} image{}.",
+                self.located.len(),
+                if self.located.len() == 1 { "" } else { "s" }
+            ));
+        }
+
+        content
+    }
+}
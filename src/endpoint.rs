@@ -0,0 +1,73 @@
+use std::env;
+
+// Discord's API and CDN are normally used directly, but both can be replaced,
+// such as with a local mock server for tests or a corporate proxy, by setting
+// these environment variables before starting the program
+pub const API_VARIABLE: &str = "DISCORD_API_BASE";
+pub const CDN_VARIABLE: &str = "DISCORD_CDN_BASE";
+
+pub const DEFAULT_API: &str = "https://discord.com/api/v10";
+pub const DEFAULT_CDN: &str = "https://cdn.discordapp.com";
+
+// Attachments are served from either of these, depending on how old they are
+const CDN_HOSTS: [&str; 3] = [
+    "https://cdn.discordapp.com",
+    "https://media.discordapp.net",
+    "https://cdn.discord.com",
+];
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Endpoints {
+    pub api: String,
+    pub cdn: String,
+}
+
+impl Default for Endpoints {
+    fn default() -> Self {
+        Endpoints {
+            api: String::from(DEFAULT_API),
+            cdn: String::from(DEFAULT_CDN),
+        }
+    }
+}
+
+impl Endpoints {
+    // The CLI reads the bases from the environment, falling back to Discord's
+    // own for any that isn't set
+    pub fn from_env() -> Endpoints {
+        let read = |name: &str, default: &str| {
+            env::var(name)
+                .ok()
+                .map(|value| value.trim().trim_end_matches('/').to_string())
+                .filter(|value| !value.is_empty())
+                .unwrap_or_else(|| String::from(default))
+        };
+
+        Endpoints {
+            api: read(API_VARIABLE, DEFAULT_API),
+            cdn: read(CDN_VARIABLE, DEFAULT_CDN),
+        }
+    }
+
+    // `path` is relative to the API's base, such as `channels/{id}/messages`
+    pub fn api(&self, path: &str) -> String {
+        format!(
+            "{}/{}",
+            self.api.trim_end_matches('/'),
+            path.trim_start_matches('/')
+        )
+    }
+
+    // Attachment URLs sent by the API point to Discord's CDN, so they're
+    // rewritten to use the selected one. URLs on other hosts are left as they
+    // are.
+    pub fn cdn(&self, url: &str) -> String {
+        for host in CDN_HOSTS.iter() {
+            if let Some(rest) = url.strip_prefix(host) {
+                return format!("{}{}", self.cdn.trim_end_matches('/'), rest);
+            }
+        }
+
+        url.to_string()
+    }
+}
@@ -0,0 +1,94 @@
+use {
+    reqwest::{Response, StatusCode},
+    serde_json::Value,
+    std::{fmt, io},
+};
+
+// Anything that can stop a download is described by this `enum`, so that
+// instead of the program crashing, the CLI can explain what went wrong and
+// tools using the `Downloader` can decide what to do about it
+#[derive(Debug)]
+pub enum Error {
+    // The request couldn't be sent, or its response couldn't be read
+    Http(reqwest::Error),
+    // Discord answered, but with an error, which includes a code listed in
+    // https://discord.com/developers/docs/topics/opcodes-and-status-codes
+    Discord {
+        status: u16,
+        code: u64,
+        message: String,
+    },
+    Io(io::Error),
+    // Something Discord sent, such as an attachment's URL, wasn't in the
+    // expected format
+    Parse(String),
+    // The bot is missing access to the channel, or a permission it needs
+    Permission(String),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Http(err) => write!(f, "Request failed: {}", err),
+            Error::Discord {
+                status,
+                code,
+                message,
+            } => write!(
+                f,
+                "Discord responded with {} (error {}): {}",
+                status, code, message
+            ),
+            Error::Io(err) => write!(f, "{}", err),
+            Error::Parse(message) => write!(f, "{}", message),
+            Error::Permission(message) => {
+                write!(f, "The bot doesn't have permission: {}", message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<reqwest::Error> for Error {
+    fn from(err: reqwest::Error) -> Error {
+        Error::Http(err)
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Error {
+        Error::Io(err)
+    }
+}
+
+// Discord's errors come with a JSON body containing a `code` and a `message`,
+// which are much more useful than the status alone, so every response is
+// checked with this before it's used
+pub(crate) async fn check(res: Response) -> Result<Response> {
+    let status = res.status();
+
+    if status.is_success() {
+        return Ok(res);
+    }
+
+    let body: Value = res.json().await.unwrap_or(Value::Null);
+    let code = body["code"].as_u64().unwrap_or(0);
+    let message = body["message"]
+        .as_str()
+        .unwrap_or_else(|| status.canonical_reason().unwrap_or("Unknown error"))
+        .to_string();
+
+    // `50001` is "Missing Access" and `50013` is "Missing Permissions"
+    if status == StatusCode::FORBIDDEN || code == 50001 || code == 50013 {
+        Err(Error::Permission(message))
+    } else {
+        Err(Error::Discord {
+            status: status.as_u16(),
+            code,
+            message,
+        })
+    }
+}
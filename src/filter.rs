@@ -0,0 +1,123 @@
+use {
+    regex::Regex,
+    serenity::model::channel::{Attachment, Message},
+};
+
+// The filters are stored in their own `struct` so that the `Config` one doesn't
+// become a huge list of fields, and so that `run::all()` can simply ask it if
+// an image should be downloaded or not.
+//
+// Just like the Start Date and Maximum Image Number, zero is used to represent
+// no limit, which is also what `Default` sets every field to.
+#[derive(Clone, Default)]
+pub struct Filter {
+    pub min_width: u64,
+    pub max_width: u64,
+    pub min_height: u64,
+    pub max_height: u64,
+    // The aspect ratio is calculated as `width / height`, so landscape images
+    // are above `1.0` and portrait ones below it
+    pub min_ratio: f64,
+    pub max_ratio: f64,
+    // The file size is stored in bytes, as that's the unit Discord uses
+    pub min_size: u64,
+    pub max_size: u64,
+    // Keywords have to appear as whole words (or phrases) in the message,
+    // while substrings can be part of a longer word, and both ignore case
+    pub keywords: Vec<String>,
+    pub substrings: Vec<String>,
+    pub pattern: Option<Regex>,
+    // If this is set, messages that match the text filters are skipped
+    // instead of being the only ones that are kept
+    pub invert: bool,
+}
+
+impl Filter {
+    // This checks if an attachment's dimensions and size are within the
+    // selected ranges, so that tiny emojis or huge raw photos can be skipped
+    // before they're even downloaded
+    pub fn attachment(&self, att: &Attachment) -> bool {
+        // Attachments that aren't images don't have dimensions, however those
+        // are already skipped by `run::all()`, so they're just treated as
+        // being 0x0 here
+        let width = att.width.unwrap_or(0);
+        let height = att.height.unwrap_or(0);
+
+        if !within(width, self.min_width, self.max_width)
+            || !within(height, self.min_height, self.max_height)
+            || !within(att.size, self.min_size, self.max_size)
+        {
+            return false;
+        }
+
+        // The ratio is only checked if at least one bound was set, which also
+        // avoids dividing by zero for images with no height
+        if self.min_ratio > 0.0 || self.max_ratio > 0.0 {
+            if height == 0 {
+                return false;
+            }
+
+            let ratio = width as f64 / height as f64;
+
+            if (self.min_ratio > 0.0 && ratio < self.min_ratio)
+                || (self.max_ratio > 0.0 && ratio > self.max_ratio)
+            {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    // This checks if a message's text mentions any of the selected keywords or
+    // matches the regular expression, so that only images posted about a
+    // specific product or store are downloaded
+    pub fn message(&self, msg: &Message) -> bool {
+        // If no text filters were selected, every message is accepted, even
+        // when the filter is inverted, as there's nothing to exclude
+        if self.keywords.is_empty() && self.substrings.is_empty() && self.pattern.is_none() {
+            return true;
+        }
+
+        let content = msg.content.to_lowercase();
+
+        // Any match is enough, as the filters are meant to be alternative ways
+        // of describing what the messages should be about
+        let matched = self
+            .keywords
+            .iter()
+            .any(|keyword| contains_word(&content, &keyword.to_lowercase()))
+            || self
+                .substrings
+                .iter()
+                .any(|substring| content.contains(&substring.to_lowercase()))
+            || self
+                .pattern
+                .as_ref()
+                .is_some_and(|pattern| pattern.is_match(&msg.content));
+
+        matched != self.invert
+    }
+}
+
+// A keyword only counts if the characters around it aren't letters or numbers,
+// so that `Jordan 1` doesn't match `Jordan 11`
+fn contains_word(content: &str, word: &str) -> bool {
+    if word.is_empty() {
+        return false;
+    }
+
+    content.match_indices(word).any(|(start, _)| {
+        let end = start + word.len();
+
+        let before = content[..start].chars().next_back();
+        let after = content[end..].chars().next();
+
+        !before.is_some_and(char::is_alphanumeric) && !after.is_some_and(char::is_alphanumeric)
+    })
+}
+
+// Every range check works the same way, with a zero bound being ignored
+fn within(value: u64, min: u64, max: u64) -> bool {
+    (min == 0 || value >= min) && (max == 0 || value <= max)
+}
@@ -0,0 +1,76 @@
+use {
+    image::{DynamicImage, ImageOutputFormat, ImageResult, Rgb, RgbImage, Rgba},
+    std::{fs::File, io::BufWriter, path::Path},
+};
+
+// Every image the program generates itself is written as either a PNG or a
+// JPEG, since those are the formats every design tool can open. JPEGs also
+// store their quality, from 1 to 100, which is the only setting the `image`
+// crate's encoder supports.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    Png,
+    Jpeg(u8),
+}
+
+impl Format {
+    // The quality used when the user only types `JPEG`, which is also the
+    // `image` crate's own default
+    pub const DEFAULT_QUALITY: u8 = 75;
+
+    // This reads formats such as `png`, `jpg`, or `jpeg:90`, which is how
+    // they're typed in the prompts
+    pub fn parse(input: &str) -> Option<Format> {
+        let input = input.trim().to_lowercase();
+        let mut parts = input.splitn(2, ':');
+
+        match (parts.next(), parts.next()) {
+            (Some("png"), None) => Some(Format::Png),
+            (Some("jpg"), None) | (Some("jpeg"), None) => {
+                Some(Format::Jpeg(Format::DEFAULT_QUALITY))
+            }
+            (Some("jpg"), Some(quality)) | (Some("jpeg"), Some(quality)) => match quality.parse() {
+                Ok(quality) if (1..=100).contains(&quality) => Some(Format::Jpeg(quality)),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Format::Png => "png",
+            Format::Jpeg(_) => "jpg",
+        }
+    }
+
+    pub fn write(&self, img: &DynamicImage, path: &Path) -> ImageResult<()> {
+        let mut file = BufWriter::new(File::create(path)?);
+
+        match self {
+            Format::Png => img.write_to(&mut file, ImageOutputFormat::Png),
+            // JPEGs don't support transparency, so the image is flattened
+            // first, otherwise the encoder would refuse RGBA images
+            Format::Jpeg(quality) => DynamicImage::ImageRgb8(flatten(img))
+                .write_to(&mut file, ImageOutputFormat::Jpeg(*quality)),
+        }
+    }
+}
+
+// Transparent pixels are blended onto a white background, as simply dropping
+// the alpha channel would reveal whatever color they were hiding, which is
+// often black
+pub fn flatten(img: &DynamicImage) -> RgbImage {
+    if !img.color().has_alpha() {
+        return img.to_rgb8();
+    }
+
+    let rgba = img.to_rgba8();
+
+    RgbImage::from_fn(rgba.width(), rgba.height(), |x, y| {
+        let Rgba([r, g, b, a]) = *rgba.get_pixel(x, y);
+        let blend = |c: u8| ((c as u32 * a as u32 + 255 * (255 - a as u32)) / 255) as u8;
+
+        Rgb([blend(r), blend(g), blend(b)])
+    })
+}
@@ -0,0 +1,188 @@
+use {
+    crate::listing::Match,
+    std::{
+        collections::BTreeSet,
+        fs::write,
+        io,
+        path::{Path, PathBuf},
+    },
+};
+
+// The gallery is a single page saved next to the images, which links to them
+// with relative paths so that the folder can be moved, zipped or uploaded
+// without breaking it
+pub const FILE: &str = "index.html";
+
+// Every saved image, along with the details of the message it came from
+#[derive(Clone, Debug)]
+pub struct Entry {
+    pub image: Match,
+    pub file: PathBuf,
+    pub thumbnail: Option<PathBuf>,
+}
+
+// Messages are linked to using the server they were sent in, or `@me` for
+// direct messages, as Discord doesn't include the server in the messages
+// themselves
+pub fn save(entries: &[Entry], dir: &Path, guild: Option<u64>) -> io::Result<PathBuf> {
+    let file = dir.join(FILE);
+    let guild = guild.map_or_else(|| String::from("@me"), |guild| guild.to_string());
+
+    // The authors are listed alphabetically in the filter
+    let authors: BTreeSet<&str> = entries.iter().map(|e| e.image.author.as_str()).collect();
+
+    let mut options = String::new();
+    for author in &authors {
+        options.push_str(&format!(
+            "<option value=\"{0}\">{0}</option>",
+            escape(author)
+        ));
+    }
+
+    let mut figures = String::new();
+    for entry in entries {
+        let image = relative(&entry.file, dir);
+        let thumb = entry
+            .thumbnail
+            .as_ref()
+            .map_or_else(|| image.clone(), |thumb| relative(thumb, dir));
+        let link = format!(
+            "https://discord.com/channels/{}/{}/{}",
+            guild, entry.image.channel, entry.image.message
+        );
+
+        // The date is cut down to the day and time, without the seconds
+        let date = entry.image.date.replace('T', " ");
+        let date = date.get(..16).unwrap_or(&date);
+
+        figures.push_str(&format!(
+            concat!(
+                "<figure data-author=\"{author}\">",
+                "<a href=\"{image}\">",
+                "<img src=\"{thumb}\" alt=\"{name}\" loading=\"lazy\"></a>",
+                "<figcaption><span>{author}</span> ",
+                "<a href=\"{link}\" target=\"_blank\">{date}</a></figcaption>",
+                "</figure>\n"
+            ),
+            author = escape(&entry.image.author),
+            image = escape(&image),
+            thumb = escape(&thumb),
+            name = escape(&entry.image.filename),
+            link = escape(&link),
+            date = date,
+        ));
+    }
+
+    let html = TEMPLATE
+        .replace("{count}", &entries.len().to_string())
+        .replace("{options}", &options)
+        .replace("{figures}", &figures);
+
+    write(&file, html)?;
+
+    Ok(file)
+}
+
+// Paths use forward slashes in HTML, even on Windows
+fn relative(file: &Path, dir: &Path) -> String {
+    file.strip_prefix(dir)
+        .unwrap_or(file)
+        .components()
+        .map(|part| part.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+// Usernames can contain anything, so they're escaped before being added,
+// including braces, which would otherwise be mistaken for the template's
+// placeholders
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+        .replace('{', "&#123;")
+}
+
+// Everything is kept in one file, without any external scripts or styles, so
+// that the gallery also works offline
+const TEMPLATE: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>Discord Images</title>
+<style>
+body { margin: 0; padding: 16px; background: #1e1f22; color: #dbdee1; font-family: sans-serif; }
+header { display: flex; align-items: center; gap: 16px; margin-bottom: 16px; }
+h1 { font-size: 20px; margin: 0; }
+select { padding: 4px; }
+main { display: grid; grid-template-columns: repeat(auto-fill, minmax(200px, 1fr)); gap: 12px; }
+figure { margin: 0; background: #2b2d31; border-radius: 8px; overflow: hidden; }
+figure.hidden { display: none; }
+figure img { display: block; width: 100%; height: 200px; object-fit: cover; cursor: zoom-in; }
+figcaption { padding: 8px; font-size: 13px; display: flex; justify-content: space-between; gap: 8px; }
+a { color: #00a8fc; }
+#lightbox { position: fixed; inset: 0; background: rgba(0, 0, 0, 0.9); display: none; flex-direction: column; align-items: center; justify-content: center; }
+#lightbox.open { display: flex; }
+#lightbox img { max-width: 95vw; max-height: 85vh; }
+#lightbox p { margin-top: 12px; }
+</style>
+</head>
+<body>
+<header>
+<h1>{count} Discord Images</h1>
+<label>Author <select id="author"><option value="">Everyone</option>{options}</select></label>
+</header>
+<main>
+{figures}</main>
+<div id="lightbox"><img alt=""><p></p></div>
+<script>
+const figures = Array.from(document.querySelectorAll("figure"));
+const lightbox = document.getElementById("lightbox");
+let current = -1;
+
+document.getElementById("author").addEventListener("change", (event) => {
+  for (const figure of figures) {
+    figure.classList.toggle("hidden", event.target.value !== "" && figure.dataset.author !== event.target.value);
+  }
+});
+
+function show(index) {
+  const visible = figures.filter((figure) => !figure.classList.contains("hidden"));
+  if (visible.length === 0) return;
+  current = (index + visible.length) % visible.length;
+  const link = visible[current].querySelector("a");
+  lightbox.querySelector("img").src = link.getAttribute("href");
+  lightbox.querySelector("p").innerHTML = visible[current].querySelector("figcaption").innerHTML;
+  lightbox.classList.add("open");
+}
+
+function hide() {
+  lightbox.classList.remove("open");
+  current = -1;
+}
+
+for (const figure of figures) {
+  figure.querySelector("a").addEventListener("click", (event) => {
+    event.preventDefault();
+    const visible = figures.filter((figure) => !figure.classList.contains("hidden"));
+    show(visible.indexOf(figure));
+  });
+}
+
+lightbox.addEventListener("click", (event) => {
+  if (event.target === lightbox) hide();
+});
+
+document.addEventListener("keydown", (event) => {
+  if (current < 0) return;
+  if (event.key === "Escape") hide();
+  if (event.key === "ArrowRight") show(current + 1);
+  if (event.key === "ArrowLeft") show(current - 1);
+});
+</script>
+</body>
+</html>
+"#;
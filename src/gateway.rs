@@ -0,0 +1,75 @@
+use {
+    crate::config::Config,
+    serenity::{
+        client::{
+            bridge::gateway::{
+                GatewayIntents, ShardManager, ShardManagerError, ShardManagerOptions,
+            },
+            EventHandler,
+        },
+        framework::{Framework, StandardFramework},
+        gateway::GatewayError,
+        http::Http,
+        prelude::{Mutex, RwLock, TypeMap},
+        CacheAndHttp, Error, Result,
+    },
+    std::sync::Arc,
+};
+
+// Watch and Bot Modes both connect to Discord's gateway, which is normally done
+// with `serenity`'s `Client`. Its builder always makes its own HTTP client
+// though, which ignores the proxy, certificates and user agent that were set
+// up, so the connection is put together here instead, using the same client as
+// the downloads. Only the websocket itself still connects directly.
+pub async fn connect<H: EventHandler + 'static>(
+    selected: &Config,
+    handler: H,
+    intents: GatewayIntents,
+) -> Result<()> {
+    let mut http = Http::new(
+        Arc::new(selected.client.clone()),
+        &format!("Bot {}", selected.token),
+    );
+
+    // Slash commands are registered and answered for the bot's application,
+    // whose ID has to be known before connecting
+    http.application_id = http.get_current_application_info().await?.id.0;
+
+    let mut cache_and_http = CacheAndHttp::default();
+    cache_and_http.http = Arc::new(http);
+    let cache_and_http = Arc::new(cache_and_http);
+
+    let ws_url = Arc::new(Mutex::new(cache_and_http.http.get_gateway().await?.url));
+
+    // Nothing uses commands written in messages, but `serenity` still needs a
+    // framework, and a single shard is plenty for the few servers a bot like
+    // this is in
+    let handler: Option<Arc<dyn EventHandler>> = Some(Arc::new(handler));
+    let framework: Arc<Box<dyn Framework + Send + Sync>> =
+        Arc::new(Box::new(StandardFramework::new()));
+
+    let (manager, mut monitor) = ShardManager::new(ShardManagerOptions {
+        data: &Arc::new(RwLock::new(TypeMap::new())),
+        event_handler: &handler,
+        raw_event_handler: &None,
+        framework: &framework,
+        shard_index: 0,
+        shard_init: 1,
+        shard_total: 1,
+        ws_url: &ws_url,
+        cache_and_http: &cache_and_http,
+        intents,
+    })
+    .await;
+
+    manager.lock().await.initialize()?;
+
+    // This only returns once the connection can't be recovered
+    monitor.run().await.map_err(|err| {
+        Error::Gateway(match err {
+            ShardManagerError::DisallowedGatewayIntents => GatewayError::DisallowedGatewayIntents,
+            ShardManagerError::InvalidGatewayIntents => GatewayError::InvalidGatewayIntents,
+            ShardManagerError::InvalidToken => GatewayError::InvalidAuthentication,
+        })
+    })
+}
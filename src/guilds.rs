@@ -0,0 +1,176 @@
+use {
+    crate::{
+        endpoint::Endpoints,
+        error::{check, Error, Result},
+    },
+    reqwest::Client,
+    serde_json::Value,
+    std::collections::HashMap,
+};
+
+// Only channels with messages can contain images, which are regular text
+// channels, announcement channels and the three kinds of threads
+const TEXT: u64 = 0;
+const ANNOUNCEMENT: u64 = 5;
+const THREADS: [u64; 3] = [10, 11, 12];
+const CATEGORY: u64 = 4;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Guild {
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Channel {
+    pub id: String,
+    pub name: String,
+    // Threads are listed right below the channel they were started in
+    pub thread: bool,
+}
+
+// These are the servers the bot was added to, which are the only ones it can
+// download images from
+pub async fn list(client: &Client, endpoints: &Endpoints, token: &str) -> Result<Vec<Guild>> {
+    let guilds = get(client, endpoints, token, "users/@me/guilds").await?;
+
+    Ok(guilds
+        .as_array()
+        .map(|guilds| {
+            guilds
+                .iter()
+                .filter_map(|guild| {
+                    Some(Guild {
+                        id: guild["id"].as_str()?.to_string(),
+                        name: guild["name"].as_str()?.to_string(),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default())
+}
+
+// The server's text channels that the bot can read, sorted the same way
+// Discord shows them, with their active threads
+pub async fn channels(
+    client: &Client,
+    endpoints: &Endpoints,
+    token: &str,
+    guild: &str,
+) -> Result<Vec<Channel>> {
+    let channels = get(
+        client,
+        endpoints,
+        token,
+        &format!("guilds/{}/channels", guild),
+    )
+    .await?;
+    let threads = get(
+        client,
+        endpoints,
+        token,
+        &format!("guilds/{}/threads/active", guild),
+    )
+    .await?;
+
+    let channels = channels.as_array().map(Vec::as_slice).unwrap_or_default();
+
+    // Discord shows the channels without a category first, and then each
+    // category with its own channels, all of them sorted by their positions,
+    // which only count within the same category
+    let categories: HashMap<&str, (u64, u64)> = channels
+        .iter()
+        .filter(|channel| channel["type"].as_u64() == Some(CATEGORY))
+        .filter_map(|category| Some((category["id"].as_str()?, order(category))))
+        .collect();
+
+    let mut channels: Vec<&Value> = channels
+        .iter()
+        .filter(|channel| {
+            let kind = channel["type"].as_u64();
+            kind == Some(TEXT) || kind == Some(ANNOUNCEMENT)
+        })
+        .collect();
+    channels.sort_by_key(|channel| {
+        let category = channel["parent_id"]
+            .as_str()
+            .and_then(|parent| categories.get(parent));
+        (category.copied(), order(channel))
+    });
+
+    let threads: Vec<&Value> = threads["threads"]
+        .as_array()
+        .map(|threads| {
+            threads
+                .iter()
+                .filter(|thread| THREADS.contains(&thread["type"].as_u64().unwrap_or(0)))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let mut list = Vec::new();
+
+    for channel in channels {
+        let id = match channel["id"].as_str() {
+            Some(id) => id,
+            None => continue,
+        };
+
+        list.push(Channel {
+            id: id.to_string(),
+            name: channel["name"].as_str().unwrap_or(id).to_string(),
+            thread: false,
+        });
+
+        for thread in threads
+            .iter()
+            .filter(|thread| thread["parent_id"].as_str() == Some(id))
+        {
+            if let Some(thread_id) = thread["id"].as_str() {
+                list.push(Channel {
+                    id: thread_id.to_string(),
+                    name: thread["name"].as_str().unwrap_or(thread_id).to_string(),
+                    thread: true,
+                });
+            }
+        }
+    }
+
+    // Discord lists every channel, even the ones the bot can't see, so each
+    // one is checked by asking for its latest message, which also needs the
+    // permission to read its history
+    let mut readable = Vec::new();
+
+    for channel in list {
+        let path = format!("channels/{}/messages?limit=1", channel.id);
+
+        match get(client, endpoints, token, &path).await {
+            Ok(_) => readable.push(channel),
+            Err(Error::Permission(_)) => {}
+            Err(err) => return Err(err),
+        }
+    }
+
+    Ok(readable)
+}
+
+// Channels with the same position are shown in the order they were created
+fn order(channel: &Value) -> (u64, u64) {
+    (
+        channel["position"].as_u64().unwrap_or(0),
+        channel["id"]
+            .as_str()
+            .and_then(|id| id.parse().ok())
+            .unwrap_or(0),
+    )
+}
+
+async fn get(client: &Client, endpoints: &Endpoints, token: &str, path: &str) -> Result<Value> {
+    let res = client
+        .get(endpoints.api(path))
+        .header("Authorization", format!("Bot {}", token))
+        .send()
+        .await?;
+
+    Ok(check(res).await?.json().await?)
+}
@@ -1,36 +1,193 @@
 use chrono::prelude::*;
 
+pub mod archive;
+pub mod bot;
+pub mod collage;
+pub mod convert;
+pub mod downloader;
+pub mod endpoint;
+pub mod error;
+pub mod filter;
+pub mod format;
+pub mod gallery;
+pub mod gateway;
+pub mod guilds;
+pub mod http;
+pub mod listing;
+pub mod metadata;
+pub mod post;
+pub mod progress;
+pub mod thumbnail;
+pub mod watch;
+
 pub mod config {
     use {
         super::*,
-        reqwest::Response,
-        std::{env::current_dir, fs::create_dir_all, io},
+        regex::Regex,
+        reqwest::{Client, Response},
+        std::{
+            env::current_dir,
+            fs::create_dir_all,
+            io,
+            path::{Path, PathBuf},
+            process::exit,
+        },
     };
 
     // This collects the config data, calling all config functions
     pub async fn all() -> Config {
-        let token = get_token().await;
-        let channel = get_channel(&token).await;
+        // The API and CDN can be replaced through environment variables, so
+        // they're read before anything is requested
+        let endpoints = endpoint::Endpoints::from_env();
+
+        // The same goes for the proxy and certificates every request uses
+        let client = get_client();
 
+        let token = get_token(&client, &endpoints).await;
+        let (mut channels, message) = get_channel(&client, &endpoints, &token).await;
+        let channel = channels.remove(0);
+
+        let mut mode = Mode::Download;
         let mut date: u64 = 0;
+        let mut until: u64 = 0;
+        let mut around: u64 = 0;
+
+        // A linked message is used as an anchor even with the default settings
+        if let Some(message) = message {
+            match get_anchor() {
+                Anchor::After => date = message,
+                Anchor::Before => until = message,
+                Anchor::Around => around = message,
+            }
+        }
         let mut quantity: u32 = 0;
+        let mut order = Order::Oldest;
+        let mut per_message = 0;
+        let mut per_author = 0;
         let mut path = default_path();
+        let mut filter = filter::Filter::default();
+        let mut collage = None;
+        let mut gallery = false;
+        let mut convert = None;
+        let mut strip_metadata = false;
+        let mut thumbnails = None;
+        let mut archive = None;
+        let mut post = None;
+        let mut dry_run = false;
+        let mut export = None;
 
         // The program won't ask the user to configure the remaining settings if
         // the Default Settings were selected
         if custom_settings() {
-            date = get_date();
-            quantity = get_quantity();
+            mode = get_mode();
+
+            // Start Dates and limits only make sense when downloading the
+            // channel's history right away, as the bot's commands include
+            // their own
+            if mode == Mode::Download || mode == Mode::Pins {
+                // The linked message already picked where to start
+                if date == 0 && around == 0 {
+                    date = get_date();
+                }
+
+                quantity = get_quantity();
+
+                // The order only matters if there's a limit
+                if quantity > 0 {
+                    order = get_order();
+                }
+
+                if confirm(&[
+                    "Should the number of images per message or per author be limited?",
+                    "Write `Yes` or `Y` to set caps, such as so one person can't fill the whole collage, or leave the line empty to skip them.",
+                ]) {
+                    per_message = get_number(
+                        &[
+                            "How many images should be taken from each message at most?",
+                            "Leave blank for an unlimited amount.",
+                        ],
+                        0,
+                    );
+                    per_author = get_number(
+                        &[
+                            "How many images should be taken from each author at most?",
+                            "Leave blank for an unlimited amount.",
+                        ],
+                        0,
+                    );
+                }
+            }
+
             path = get_path();
+            filter = get_filter();
+
+            // A dry run only lists the images, so there's nothing to ask
+            // about what should be done with them
+            if mode == Mode::Download || mode == Mode::Pins {
+                dry_run = confirm(&[
+                    "Should this be a dry run?",
+                    "Write `Yes` or `Y` to only list the matching images and their total size without downloading them, or leave the line empty to download them.",
+                ]);
+
+                if dry_run {
+                    export = get_export(&path);
+                }
+            }
+
+            if !dry_run {
+                convert = get_convert();
+                strip_metadata = confirm(&[
+                    "Should metadata, such as the location photos were taken at, be removed from the images?",
+                    "Write `Yes` or `Y` to remove it, or leave the line empty to keep the files as they were posted.",
+                ]);
+                thumbnails = get_thumbnails();
+
+                // Collages are made once the downloads are over, which never
+                // happens while watching the channel
+                if mode != Mode::Watch {
+                    collage = get_collage();
+                    gallery = confirm(&[
+                        "Should an HTML gallery of the images be made?",
+                        "Write `Yes` or `Y` to save an `index.html` page to browse them with, or leave the line empty to skip it.",
+                    ]);
+                    archive = get_archive();
+                    post = get_post(
+                        &client,
+                        &endpoints,
+                        &token,
+                        collage.is_some() || archive.is_some(),
+                    )
+                    .await;
+                }
+            }
         }
 
         // The `Config` `struct` is defined at the end of the module
         Config {
             token,
             channel,
+            channels,
+            mode,
             date,
+            until,
+            around,
             quantity,
+            per_message,
+            per_author,
+            order,
             path,
+            filter,
+            convert,
+            strip_metadata,
+            thumbnails,
+            collage,
+            gallery,
+            archive,
+            post,
+            dry_run,
+            export,
+            endpoints,
+            client,
         }
     }
 
@@ -55,73 +212,275 @@ pub mod config {
 
     // This function allows `get_token()` and `get_channel()` to use Discord-s API to
     // check if their value is valid
-    async fn api(token: &str, path: &str) -> Response {
-        reqwest::Client::new()
-            .get(format!("https://discordapp.com/api/{}", path))
+    pub(crate) async fn api(
+        client: &Client,
+        endpoints: &endpoint::Endpoints,
+        token: &str,
+        path: &str,
+    ) -> error::Result<Response> {
+        let res = client
+            .get(endpoints.api(path))
             .header("Authorization", format!("Bot {}", token))
             .send()
-            .await
-            .expect("Request Failed!")
+            .await?;
+
+        error::check(res).await
+    }
+
+    // If the proxy or certificates can't be used, nothing would work, so the
+    // program stops right away instead of asking for the other settings
+    fn get_client() -> Client {
+        match http::client(&http::Options::from_env()) {
+            Ok(client) => client,
+            Err(err) => {
+                println!("\nFailed to set up the connection: {}", err);
+                println!(
+                    "Check the `{}` and `{}` environment variables, then try again.",
+                    http::PROXY_VARIABLE,
+                    http::CA_BUNDLE_VARIABLE
+                );
+                println!("\nHit `Enter` to close!");
+
+                let mut input = String::new();
+                io::stdin().read_line(&mut input).ok();
+
+                exit(1);
+            }
+        }
     }
 
-    async fn get_token() -> String {
+    async fn get_token(client: &Client, endpoints: &endpoint::Endpoints) -> String {
         loop {
             let input = input(&["What's your bot's token?"]);
 
             // If the response's status is "OK", the Bot Token is valid,
-            // otherwise it's not, and the user is prompted again
-            if api(&input, "gateway/bot").await.status() == 200 {
-                break input;
-            } else {
-                println!("\nInvalid Bot Token!");
-                continue;
+            // otherwise it's not, and the user is prompted again, unless
+            // Discord couldn't be reached at all
+            match api(client, endpoints, &input, "gateway/bot").await {
+                Ok(_) => break input,
+                Err(error::Error::Http(err)) => {
+                    println!("\nFailed to reach Discord: {}", err);
+                    continue;
+                }
+                Err(_) => {
+                    println!("\nInvalid Bot Token!");
+                    continue;
+                }
             }
         }
     }
 
-    async fn get_channel(token: &str) -> String {
+    // If a link to a message is given, its ID is returned too, so that it can
+    // be used as an anchor for the download. Picking the channels from a list
+    // can return more than one, but never none.
+    async fn get_channel(
+        client: &Client,
+        endpoints: &endpoint::Endpoints,
+        token: &str,
+    ) -> (Vec<String>, Option<u64>) {
         loop {
-            let mut input = input(&[
+            let input = input(&[
                 "What channel are the images in?",
-                "Input the Channel ID or its link, not its name.",
+                "Input the Channel ID or its link, not its name, or the link to a message to start from.",
+                "Leave the line empty to pick the channels from the servers the bot is in.",
             ]);
 
-            // If the input contains non-numerical characters and looks like a
-            // link to a channel, the tool will try to extract the Channel ID
-            // from it
-            if input.parse::<u64>().is_err() && input.contains("discord.com/channels/") {
-                input = input
-                    .split('/')
-                    .last()
-                    .expect("Failed to parse Discord Channel Link!")
-                    .to_string();
+            if input.is_empty() {
+                let channels = pick_channels(client, endpoints, token).await;
+
+                if channels.is_empty() {
+                    continue;
+                }
+
+                break (channels, None);
             }
 
+            let message = link(&input).and_then(|(_, message)| message);
+            let input = channel_id(input);
+
             // If the response's status is "OK", the Channel ID is valid and can
             // be accessed using the inputted Bot Token, and if it's not, the
             // user is prompted again
-            if api(token, format!("channels/{}", input).as_str())
-                .await
-                .status()
-                == 200
+            match api(
+                client,
+                endpoints,
+                token,
+                format!("channels/{}", input).as_str(),
+            )
+            .await
             {
-                break input;
+                Ok(_) => break (vec![input], message),
+                Err(err) => {
+                    println!("\nInvalid Channel ID!");
+                    println!("The bot can't access this channel! {}", err);
+                    continue;
+                }
+            }
+        }
+    }
+
+    // Finding a Channel ID is the hardest part of using the tool for most
+    // people, so the bot's servers and their channels can be listed instead,
+    // picking them by their number. An empty list is returned if nothing was
+    // picked, to ask for the channel again.
+    async fn pick_channels(
+        client: &Client,
+        endpoints: &endpoint::Endpoints,
+        token: &str,
+    ) -> Vec<String> {
+        let servers = match guilds::list(client, endpoints, token).await {
+            Ok(servers) if !servers.is_empty() => servers,
+            Ok(_) => {
+                println!("\nThe bot isn't in any servers yet!");
+                return Vec::new();
+            }
+            Err(err) => {
+                println!("\nFailed to list the bot's servers! {}", err);
+                return Vec::new();
+            }
+        };
+
+        println!("\nThe bot is in these servers:");
+        for (n, server) in servers.iter().enumerate() {
+            println!("{}. {}", n + 1, server.name);
+        }
+
+        let server = loop {
+            let n = get_number(&["Which server are the images in?"], 0) as usize;
+
+            match servers.get(n.wrapping_sub(1)) {
+                Some(server) => break server,
+                None => println!("\nInvalid input! Pick one of the numbers above."),
+            }
+        };
+
+        let channels = match guilds::channels(client, endpoints, token, &server.id).await {
+            Ok(channels) if !channels.is_empty() => channels,
+            Ok(_) => {
+                println!(
+                    "\n{} doesn't have any text channels the bot can read!",
+                    server.name
+                );
+                return Vec::new();
+            }
+            Err(err) => {
+                println!("\nFailed to list the channels in {}! {}", server.name, err);
+                return Vec::new();
+            }
+        };
+
+        println!("\nThese are the channels in {}:", server.name);
+        for (n, channel) in channels.iter().enumerate() {
+            if channel.thread {
+                println!("{}.   ↳ {}", n + 1, channel.name);
+            } else {
+                println!("{}. #{}", n + 1, channel.name);
+            }
+        }
+
+        let picked = loop {
+            let numbers = get_list(&[
+                "Which channels should the images be downloaded from?",
+                "Input their numbers separated by commas, such as `1, 3`.",
+            ]);
+
+            let picked: Option<Vec<&guilds::Channel>> = numbers
+                .iter()
+                .map(|n| {
+                    n.parse::<usize>()
+                        .ok()
+                        .and_then(|n| channels.get(n.wrapping_sub(1)))
+                })
+                .collect();
+
+            match picked {
+                Some(picked) if !picked.is_empty() => break picked,
+                _ => println!("\nInvalid input! Pick one or more of the numbers above."),
+            }
+        };
+
+        // Only the listed channels can be picked, which were already checked,
+        // so all that's left is ignoring the ones picked twice
+        let mut ids: Vec<String> = Vec::new();
+
+        for channel in picked {
+            if !ids.contains(&channel.id) {
+                ids.push(channel.id.clone());
+            }
+        }
+
+        ids
+    }
+
+    // If the input contains non-numerical characters and looks like a link to a
+    // channel, the tool will try to extract the Channel ID from it
+    fn channel_id(input: String) -> String {
+        if input.parse::<u64>().is_err() {
+            if let Some((channel, _)) = link(&input) {
+                return channel;
+            }
+        }
+
+        input
+    }
+
+    // Links look like `discord.com/channels/{server}/{channel}`, with the
+    // message's ID added at the end when linking to one, so the Channel ID is
+    // always the second part after `channels/`, even in direct messages, where
+    // the server is `@me`
+    fn link(input: &str) -> Option<(String, Option<u64>)> {
+        let (_, rest) = input.trim().split_once("/channels/")?;
+        let mut parts = rest.trim_end_matches('/').split('/').skip(1);
+
+        let channel = parts.next().filter(|id| !id.is_empty())?.to_string();
+        let message = parts.next().and_then(|id| id.parse().ok());
+
+        Some((channel, message))
+    }
+
+    // Messages can mark where the download starts or ends, or be the middle
+    // of the messages that are downloaded
+    fn get_anchor() -> Anchor {
+        loop {
+            let input = input(&[
+                "Which images should be downloaded from around the linked message?",
+                "Write `After`, `A`, or leave the line empty to download the ones posted after it.",
+                "Input `Before` or `B` to download the ones posted before it.",
+                "Input `Around` or `R` to only download the ones posted around it.",
+            ])
+            .to_lowercase();
+
+            if input == "after" || input == "a" || input.is_empty() {
+                break Anchor::After;
+            } else if input == "before" || input == "b" {
+                break Anchor::Before;
+            } else if input == "around" || input == "r" {
+                break Anchor::Around;
             } else {
-                println!("\nInvalid Channel ID!");
-                println!("The bot can't access this channel!");
+                println!("\nInvalid input!");
                 continue;
             }
         }
     }
 
+    enum Anchor {
+        After,
+        Before,
+        Around,
+    }
+
     // The only reason this function is asynchronous is that, for reasons I
     // can't explain, the program would crash if I didn't
     fn custom_settings() -> bool {
         println!("\nAlthough the program allows for several customizations, most users use the default settings.\n");
         println!("The default settings are:");
+        println!("- Download the images that were already posted");
         println!("- Download images of any age");
         println!("- Save an unlimited amount of photos");
         println!("- Store pictures in `./Discord Images`");
+        println!("- Include images of any size, from any message");
+        println!("- Keep every image in its original format, with its metadata");
+        println!("- Don't make thumbnails, a collage or an archive, or post the results");
 
         loop {
             let input = input(&[
@@ -141,6 +500,32 @@ pub mod config {
         }
     }
 
+    fn get_mode() -> Mode {
+        loop {
+            let input = input(&[
+                "What should the program do?",
+                "Write `Download`, `D`, or leave the line empty to download the images that were already posted.",
+                "Input `Pins` or `P` to only download the images in pinned messages.",
+                "Input `Watch` or `W` to keep running and save new images as they're posted.",
+                "Input `Bot` or `B` to let people start downloads with the `/archive` command in Discord.",
+            ])
+            .to_lowercase();
+
+            if input == "download" || input == "d" || input.is_empty() {
+                break Mode::Download;
+            } else if input == "pins" || input == "p" {
+                break Mode::Pins;
+            } else if input == "watch" || input == "w" {
+                break Mode::Watch;
+            } else if input == "bot" || input == "b" {
+                break Mode::Bot;
+            } else {
+                println!("\nInvalid input!");
+                continue;
+            }
+        }
+    }
+
     // While I initially saved the Start Date as an instance of
     // `chrono::DateTime`, I opted for a Snowflake instead, this time saved as a
     // `u64`, so that I could compare its value to the IDs of the collected
@@ -151,10 +536,14 @@ pub mod config {
             // doesn't like long lines
             let input = input(&[
                 "How far back should we search?",
-                "Leave blank to download all images.",
+                "Input a date as `DD/MM/YY`, or a message's link to start after it, or leave blank to download all images.",
             ])
             .to_lowercase();
 
+            if let Some((_, Some(message))) = link(&input) {
+                break message;
+            }
+
             // As dumb as it sounds, `Default` is a recognized value because I
             // liked how it looked in a screenshot of the program's interface I
             // sent a friend while writing it
@@ -252,8 +641,7 @@ pub mod config {
                         continue;
                     */
                     } else {
-                        // This converts the UNIX timestamp to a Snowflake
-                        break (date.timestamp_millis() as u64 - 1420070400000) << 22;
+                        break snowflake(date);
                     }
                 } else {
                     println!("Invalid input! Please write a date as `DD/MM/YY`.");
@@ -263,6 +651,14 @@ pub mod config {
         }
     }
 
+    // This converts a date's UNIX timestamp to a Snowflake, which is also used
+    // by the bot to read the `since` option of its commands
+    pub fn snowflake(date: DateTime<Utc>) -> u64 {
+        // Snowflakes store the milliseconds since the first second of 2015,
+        // known as the Discord Epoch, in their first 42 bits
+        (date.timestamp_millis().max(1420070400000) as u64 - 1420070400000) << 22
+    }
+
     fn get_quantity() -> u32 {
         loop {
             // While I normally separate multiline messages into more than
@@ -291,6 +687,71 @@ pub mod config {
         }
     }
 
+    fn get_order() -> Order {
+        loop {
+            let input = input(&[
+                "Should the oldest or the newest images be downloaded first?",
+                "Write `Oldest`, `O`, or leave the line empty to start from the Start Date.",
+                "Input `Newest` or `N` to start from the most recent images, such as to get the latest ones.",
+                "Input `Random` or `R` to pick a random sample from the whole range, such as for a collage.",
+                "Input `Reactions` or `T` to pick the images with the most reactions.",
+            ])
+            .to_lowercase();
+
+            if input == "oldest" || input == "o" || input.is_empty() {
+                break Order::Oldest;
+            } else if input == "newest" || input == "n" {
+                break Order::Newest;
+            } else if input == "random" || input == "r" {
+                break Order::Random(get_seed());
+            } else if input == "reactions" || input == "t" {
+                break Order::Reactions(get_emoji());
+            } else {
+                println!("\nInvalid input!");
+                continue;
+            }
+        }
+    }
+
+    // Using the same seed picks the same images again, as long as the channel
+    // hasn't changed, while a blank line picks a new one from the clock
+    fn get_seed() -> u64 {
+        loop {
+            let input = input(&[
+                "Which seed should be used to pick the images?",
+                "Leave blank to use a new one, which will be shown once the download is done.",
+            ]);
+
+            if input.is_empty() {
+                break Utc::now().timestamp_nanos() as u64;
+            }
+
+            match input.parse::<u64>() {
+                Ok(seed) => break seed,
+                Err(_) => {
+                    println!("\nInvalid input!");
+                    println!("Make sure to either select a positive integer or to hit `Enter` immediately.");
+                    continue;
+                }
+            }
+        }
+    }
+
+    // Custom emojis can't be pasted in a terminal, so they're written using
+    // their name or ID instead
+    fn get_emoji() -> Option<String> {
+        let input = input(&[
+            "Which emoji should the reactions be counted for?",
+            "Write an emoji, such as 🔥, or a custom emoji's name or ID, or leave the line empty to count every reaction.",
+        ]);
+
+        if input.is_empty() {
+            None
+        } else {
+            Some(input)
+        }
+    }
+
     // Returning `&'static Path`s gave me so many issues I ended up saving the
     // selected path as a `String`, since Rust lifetimes can be great but are
     // definitely a double-edged sword
@@ -316,208 +777,1582 @@ pub mod config {
         }
     }
 
-    fn default_path() -> String {
-        // This was the most obvious way to create a folder in the
-        // current directory, and I'll probably keep it this way as it
-        // works and I'm too dumb to figure out a more idiomatic method
-        let path = format!(
-            "{}/Discord Images",
-            current_dir()
-                .expect("Failed to get current directory!")
-                .to_str()
-                .expect("Failed to get current directory!")
-        );
-
-        create_dir_all(&path).expect("Failed to create directory!");
+    // The list is saved in the image directory unless a full path is given
+    fn get_export(path: &str) -> Option<PathBuf> {
+        let input = input(&[
+            "Should the list of images be exported?",
+            "Write a file name ending in `.csv` or `.json` to save it, or leave the line empty to only print it.",
+        ]);
 
-        path
+        if input.is_empty() {
+            None
+        } else {
+            Some(Path::new(path).join(input))
+        }
     }
 
-    // The resoning behind the types used in the `struct` were all mentioned in
-    // the functions that generate them, so I won't repeat them
-    pub struct Config {
-        pub token: String,
-        pub channel: String,
-        pub date: u64,
-        pub quantity: u32,
-        pub path: String,
-    }
-}
+    // Since most people won't care about the size or text of the images, the
+    // filters are hidden behind one more question each instead of being asked
+    // one by one
+    fn get_filter() -> filter::Filter {
+        let mut filter = filter::Filter::default();
 
-pub mod run {
-    use {
-        super::*,
-        serenity::model::channel::Message,
-        std::{collections::HashMap, fs::write, path::Path},
-    };
+        if confirm(&[
+            "Should images be filtered by their size?",
+            "Write `Yes` or `Y` to select size limits, or leave the line empty to include all images.",
+        ]) {
+            let (min, max) = get_range(&[
+                "What width should the images have, in pixels?",
+                "Use the `MIN-MAX` format, leaving either side blank for no limit (e.g. `200-`).",
+            ]);
+            filter.min_width = min as u64;
+            filter.max_width = max as u64;
 
-    // Jut like in the `config` module, the `all()` function calls its own
-    // functions and handles the whole task. I could have simply written two
-    // functions, `config()` and `run()`, outside of modules, but I only
-    // intended for them to be used, and as far as I know hey wouldn't be able
-    // to use private functions from the modules otherwise
-    pub async fn all(selected: config::Config) {
-        // This prints an empty sline to separate he download messages from the
-        // user's last input, once again for cosmetic reasons (this is a tool
-        // for Sneaker Twitter Designers, after all)
-        println!();
+            let (min, max) = get_range(&[
+                "What height should the images have, in pixels?",
+                "Use the `MIN-MAX` format, leaving either side blank for no limit (e.g. `-4000`).",
+            ]);
+            filter.min_height = min as u64;
+            filter.max_height = max as u64;
 
-        // Since the path was saved as a String, as I explained earlier, the
-        // real path has to be created here
-        let path = Path::new(&selected.path);
+            let (min, max) = get_range(&[
+                "What aspect ratio (width / height) should the images have?",
+                "Use the `MIN-MAX` format, leaving either side blank for no limit (e.g. `0.5-2`).",
+            ]);
+            filter.min_ratio = min;
+            filter.max_ratio = max;
+
+            // Kilobytes are easier to type than bytes, so the size is
+            // converted here
+            let (min, max) = get_range(&[
+                "How big should the image files be, in KB?",
+                "Use the `MIN-MAX` format, leaving either side blank for no limit (e.g. `50-8000`).",
+            ]);
+            filter.min_size = (min * 1024.0) as u64;
+            filter.max_size = (max * 1024.0) as u64;
+        }
+
+        if confirm(&[
+            "Should messages be filtered by their text?",
+            "Write `Yes` or `Y` to search for specific words, or leave the line empty to include all messages.",
+        ]) {
+            filter.keywords = get_list(&[
+                "Which keywords should the messages mention?",
+                "Separate them with commas (e.g. `Jordan 1, #success-nike`), or leave the line empty to skip.",
+            ]);
+
+            filter.substrings = get_list(&[
+                "Which text should the messages contain, even as part of a word?",
+                "Separate them with commas (e.g. `yeezy, dunk`), or leave the line empty to skip.",
+            ]);
+
+            filter.pattern = get_pattern();
 
-        // This `HashMap` is used to keep track of the number of images
-        // downloaded and make sure they don't exceed the specified limit, while
-        // also keeping track of the amount of times each one is downloaded,
-        // ensuring that images aren't downloaded more than once
-        let mut images: HashMap<String, u32> = HashMap::new();
+            filter.invert = confirm(&[
+                "Should the matching messages be excluded instead?",
+                "Write `Yes` or `Y` to skip them, or leave the line empty to only keep them.",
+            ]);
+        }
 
-        // `after` is initialized as the Start Date, as all images should have
-        // be sent after it
-        let mut after = selected.date;
+        filter
+    }
 
+    // Yes or no questions all default to "no", as every optional feature is
+    // disabled unless the user asks for it
+    fn confirm(prompt: &[&str]) -> bool {
         loop {
-            // After getting migraines due to my completely unnecessary efforts to
-            // serialize Discord's Message API JSON responses, I decided to simply
-            // use the ones defined in the `serenity` crate, importing their
-            // `Message` struct (and making me cry for wasting so much time)
-            let res: Vec<Message> = get(&selected, after).await;
+            let input = input(prompt).to_lowercase();
 
-            // Once all messages are requested, there will be no new ones and
-            // the program will be done
-            if res.is_empty() {
-                break;
+            if input == "no" || input == "n" || input.is_empty() {
+                break false;
+            } else if input == "yes" || input == "y" {
+                break true;
             } else {
-                // The first message returned by the API is the most recent one,
-                // so after is updated to ts ID so that the next requests only
-                // includes messages sent after it
-                after = format!("{}", res.first().expect("Failed to find last message!").id)
-                    .parse()
-                    .expect("Failed to parse Message ID!");
-
-                // Since the API's response is simply an array of messages, I iterate
-                // through each one
-                for msg in res {
-                    // I immediately extract the Message ID so that I can check
-                    // if the image has been downloaded already
-                    let id = format!("{}", msg.id);
-
-                    // The program only continues if the image limit hasn't been
-                    // reached and the image hasn't been previously downloaded
-                    if images.len() < selected.quantity as usize
-                        || selected.quantity == 0 && !images.contains_key(id.as_str())
-                    {
-                        // Not all messages have attatchments, but not all attatchments are
-                        // images either, so each one must be checked
-                        for att in msg.attachments {
-                            // This checks that the attatchment is an image by checking
-                            // if a `width` property is specified
-                            if att.width.is_some() {
-                                // If it is, the image's url is accessed and the file is
-                                // saved using the `save()` function, defined below
-                                let url = att.url;
-                                save(&url, path).await;
-
-                                // The image's Message ID is added to `images`
-                                // if it isn't part of it already
-                                /*
-                                let count =
-                                */
-                                images.entry(format!("{}", msg.id)).or_insert(0);
-
-                                // The number of downloads could be checked in
-                                // the future to verify if an image has indeed
-                                // only been saved once, however that
-                                // functionality hasn't been implemented yet
-                                /*
-                                 *count += 1;
-                                 */
-                            }
-                        }
-                    } else {
-                        break;
-                    }
-                }
+                println!("\nInvalid input!");
+                continue;
             }
         }
+    }
 
-        // Once all images have been downloaded, the number found is dispayed
+    // Comma separated lists are used for keywords, and blank items are dropped
+    // so that trailing commas don't match every message
+    fn get_list(prompt: &[&str]) -> Vec<String> {
+        input(prompt)
+            .split(',')
+            .map(|item| item.trim().to_string())
+            .filter(|item| !item.is_empty())
+            .collect()
+    }
 
-        // Conditional statements are used to customize the final message
-        if images.is_empty() {
-            println!(
-                "The channel doesn't contain any images{}!",
-                if selected.date == 0 {
-                    ""
-                } else {
-                    " in the selected time range"
+    fn get_pattern() -> Option<Regex> {
+        loop {
+            let input = input(&[
+                "Which regular expression should the messages match?",
+                "Leave the line empty to skip, or add `(?i)` at the start to ignore case.",
+            ]);
+
+            if input.is_empty() {
+                break None;
+            }
+
+            match Regex::new(&input) {
+                Ok(pattern) => break Some(pattern),
+                Err(err) => {
+                    println!("\nInvalid regular expression!");
+                    println!("{}", err);
+                    continue;
                 }
-            );
-        } else {
-            println!(
-                "\nSuccessfully saved {} image{}!",
-                images.len(),
-                if images.len() == 1 { "" } else { "s" }
-            );
+            }
         }
     }
 
-    async fn get(selected: &config::Config, after: u64) -> Vec<Message> {
-        // The API is extremely simple, as shown below
-        let mut url = format!(
-            "https://discordapp.com/api/channels/{}/messages?limit=100",
-            selected.channel
+    // Converting images is mostly useful for design tools that can't open
+    // WebPs, so PNG is the default as it doesn't lose any quality
+    fn get_convert() -> Option<convert::Options> {
+        if !confirm(&[
+            "Should every image be converted to the same format?",
+            "Write `Yes` or `Y` to pick one, or leave the line empty to keep them as they were posted.",
+        ]) {
+            return None;
+        }
+
+        let defaults = convert::Options::default();
+
+        let format = get_format(
+            &[
+                "Which format should the images be converted to?",
+                "Write `PNG`, `JPEG`, or `JPEG:QUALITY` (e.g. `JPEG:90`), or leave the line empty to use PNG.",
+            ],
+            defaults.format,
         );
 
-        if after > 0 {
-            url = format!("{}&after={}", url, after);
-        }
+        let keep_original = confirm(&[
+            "Should the original files be kept?",
+            "Write `Yes` or `Y` to move them to an `originals` folder, or leave the line empty to delete them.",
+        ]);
 
-        // The authorization in the API is as basic as adding a header with the
-        // Bot Token, formatted this way
-        let auth = format!("Bot {}", selected.token);
-        reqwest::Client::new()
-            .get(url)
-            .header("Authorization", auth)
-            .send()
-            .await
-            .expect("Failed to send GET Request!")
-            // The response is serialized as a `Vec<Message>` as explained
-            // in the `all()` function
-            .json::<Vec<Message>>()
-            .await
-            .expect("Failed to parse Response!")
+        let gif = if confirm(&[
+            "Should animated GIFs be converted too?",
+            "Write `Yes` or `Y` to only keep their first frame, or leave the line empty to leave them animated.",
+        ]) {
+            convert::Gif::Flatten
+        } else {
+            convert::Gif::Keep
+        };
+
+        Some(convert::Options {
+            format,
+            keep_original,
+            gif,
+        })
     }
 
-    async fn save(url: &str, path: &Path) {
-        // Although the Message ID is specified in the `Message` `struct`, it's
-        // easier to extract it from the Image URL
-        let name = url.split('/').nth(5).expect("Failed to get image name!");
+    fn get_thumbnails() -> Option<thumbnail::Options> {
+        if !confirm(&[
+            "Should thumbnails be made for the downloaded images?",
+            "Write `Yes` or `Y` to save smaller copies in a `thumbs` folder, or leave the line empty to skip them.",
+        ]) {
+            return None;
+        }
 
-        // The filetype is also extracted from the URL
-        let ext = url
-            .split('.')
-            .last()
-            .expect("Failed to get image filetype!");
+        let defaults = thumbnail::Options::default();
+
+        let max_edge = loop {
+            let edge = get_number(
+                &[
+                    "How big should the thumbnails be, in pixels?",
+                    "This is the length of their longest side, leave the line empty to use 320 pixels.",
+                ],
+                defaults.max_edge,
+            );
+
+            if edge > 0 {
+                break edge;
+            } else {
+                println!("\nThe thumbnails can't be 0 pixels wide!");
+                continue;
+            }
+        };
+
+        let format = get_format(
+            &[
+                "Which format should the thumbnails be saved as?",
+                "Write `PNG`, `JPEG`, or `JPEG:QUALITY` (e.g. `JPEG:60`), or leave the line empty to use JPEG:80.",
+            ],
+            defaults.format,
+        );
+
+        Some(thumbnail::Options { max_edge, format })
+    }
+
+    // Collages have quite a few settings, so, just like the filters, they're
+    // only asked for if the user wants one to be made
+    fn get_collage() -> Option<collage::Options> {
+        if !confirm(&[
+            "Should the images be combined into a collage?",
+            "Write `Yes` or `Y` to make one, or leave the line empty to only download the images.",
+        ]) {
+            return None;
+        }
+
+        let defaults = collage::Options::default();
+
+        let layout = loop {
+            let input = input(&[
+                "Which layout should the collage use?",
+                "Write `Grid` or `G` to give every image the same size, or `Masonry` or `M` to keep their shapes.",
+                "Leave the line empty to use a grid.",
+            ])
+            .to_lowercase();
+
+            if input == "grid" || input == "g" || input.is_empty() {
+                break collage::Layout::Grid;
+            } else if input == "masonry" || input == "m" {
+                break collage::Layout::Masonry;
+            } else {
+                println!("\nInvalid input!");
+                continue;
+            }
+        };
+
+        let (width, height) = loop {
+            let input = input(&[
+                "How big should the collage be, in pixels?",
+                "Use the `WIDTHxHEIGHT` format, leaving the height blank to fit the images (e.g. `4096x`).",
+                "Leave the line empty to use a width of 4096 pixels.",
+            ])
+            .to_lowercase();
+
+            if input.is_empty() {
+                break (defaults.width, 0);
+            }
+
+            let size: Vec<&str> = input.split('x').map(|side| side.trim()).collect();
+
+            if size.len() != 2 {
+                println!("\nInvalid input! Please write the size as `WIDTHxHEIGHT`.");
+                continue;
+            }
+
+            let height = if size[1].is_empty() {
+                Ok(0)
+            } else {
+                size[1].parse::<u32>()
+            };
+
+            match (size[0].parse::<u32>(), height) {
+                (Ok(width), Ok(height)) if width > 0 => break (width, height),
+                _ => {
+                    println!("\nInvalid input!");
+                    println!("Make sure the width is a positive integer.");
+                    continue;
+                }
+            }
+        };
+
+        let columns = get_number(
+            &[
+                "How many columns should the collage have?",
+                "Leave the line empty to pick them automatically.",
+            ],
+            0,
+        );
+
+        let gutter = get_number(
+            &[
+                "How much space should be left between the images, in pixels?",
+                "Leave the line empty to use 8 pixels.",
+            ],
+            defaults.gutter,
+        );
+
+        let background = loop {
+            let input = input(&[
+                "What color should the background be?",
+                "Write it as a hex code (e.g. `#1A1A1A`), or leave the line empty to use white.",
+            ]);
+
+            if input.is_empty() {
+                break defaults.background;
+            }
+
+            match collage::parse_color(&input) {
+                Some(color) => break color,
+                None => {
+                    println!("\nInvalid color! Please use the `#RRGGBB` format.");
+                    continue;
+                }
+            }
+        };
+
+        let fit = if layout == collage::Layout::Grid {
+            loop {
+                let input = input(&[
+                    "How should the images fit their cells?",
+                    "Write `Cover` or leave the line empty to crop them so they fill the cells.",
+                    "Write `Contain` to shrink them so that they're fully visible.",
+                ])
+                .to_lowercase();
+
+                if input == "cover" || input.is_empty() {
+                    break collage::Fit::Cover;
+                } else if input == "contain" {
+                    break collage::Fit::Contain;
+                } else {
+                    println!("\nInvalid input!");
+                    continue;
+                }
+            }
+        } else {
+            defaults.fit
+        };
+
+        let format = get_format(
+            &[
+                "Which format should the collage be saved as?",
+                "Write `PNG`, `JPEG`, or `JPEG:QUALITY` (e.g. `JPEG:90`), or leave the line empty to use PNG.",
+            ],
+            defaults.format,
+        );
+
+        Some(collage::Options {
+            layout,
+            width,
+            height,
+            columns,
+            gutter,
+            background,
+            fit,
+            format,
+        })
+    }
+
+    fn get_archive() -> Option<archive::Options> {
+        let kind = loop {
+            let input = input(&[
+                "Should the images be saved to an archive?",
+                "Write `Zip` or `Tar` to create a `.zip` or `.tar.gz` file, or leave the line empty to skip it.",
+            ])
+            .to_lowercase();
+
+            if input == "no" || input == "n" || input.is_empty() {
+                return None;
+            } else if input == "zip" || input == "z" {
+                break archive::Kind::Zip;
+            } else if input == "tar" || input == "t" || input == "tar.gz" {
+                break archive::Kind::TarGz;
+            } else {
+                println!("\nInvalid input!");
+                continue;
+            }
+        };
+
+        let keep_folder = confirm(&[
+            "Should the images also be kept in the image directory?",
+            "Write `Yes` or `Y` to keep them, or leave the line empty to only keep the archive.",
+        ]);
+
+        Some(archive::Options { kind, keep_folder })
+    }
+
+    // The results can be posted either by the bot or through a webhook, which
+    // is told apart from a channel by its URL
+    async fn get_post(
+        client: &Client,
+        endpoints: &endpoint::Endpoints,
+        token: &str,
+        files: bool,
+    ) -> Option<post::Options> {
+        if !confirm(&[
+            "Should the results be posted on Discord once the download is done?",
+            "Write `Yes` or `Y` to post a summary, or leave the line empty to skip it.",
+        ]) {
+            return None;
+        }
+
+        let target = loop {
+            let input = input(&[
+                "Where should the results be posted?",
+                "Input a Channel ID or link for the bot to post in, or a webhook's URL.",
+            ]);
+
+            if input.contains("/api/webhooks/") {
+                break post::Target::Webhook(input);
+            }
+
+            let channel = channel_id(input);
+
+            match api(
+                client,
+                endpoints,
+                token,
+                format!("channels/{}", channel).as_str(),
+            )
+            .await
+            {
+                Ok(_) => break post::Target::Channel(channel),
+                Err(err) => {
+                    println!("\nInvalid Channel ID!");
+                    println!("The bot can't access this channel! {}", err);
+                    continue;
+                }
+            }
+        };
+
+        // There's nothing to upload unless a collage or an archive is being
+        // made
+        let attach = files
+            && confirm(&[
+                "Should the collage and archive be uploaded with the results?",
+                "Write `Yes` or `Y` to upload them, or leave the line empty to only post the summary.",
+            ]);
+
+        let limit = if attach {
+            get_number(
+                &[
+                    "What's the server's upload limit, in MB?",
+                    "Leave the line empty to use 10 MB, the limit for servers without boosts.",
+                ],
+                (post::DEFAULT_LIMIT / 1024 / 1024) as u32,
+            ) as u64
+                * 1024
+                * 1024
+        } else {
+            post::DEFAULT_LIMIT
+        };
+
+        Some(post::Options {
+            target,
+            attach,
+            limit,
+        })
+    }
+
+    // Numeric settings that have a default value use this instead of parsing
+    // the input themselves
+    fn get_number(prompt: &[&str], default: u32) -> u32 {
+        loop {
+            let input = input(prompt);
+
+            if input.is_empty() {
+                break default;
+            }
+
+            match input.parse::<u32>() {
+                Ok(num) => break num,
+                Err(_) => {
+                    println!("\nInvalid input!");
+                    println!("Make sure to either select a positive integer or to hit `Enter` immediately.");
+                    continue;
+                }
+            }
+        }
+    }
+
+    fn get_format(prompt: &[&str], default: format::Format) -> format::Format {
+        loop {
+            let input = input(prompt);
+
+            if input.is_empty() {
+                break default;
+            }
+
+            match format::Format::parse(&input) {
+                Some(format) => break format,
+                None => {
+                    println!("\nInvalid format!");
+                    println!("JPEG quality has to be a number between 1 and 100.");
+                    continue;
+                }
+            }
+        }
+    }
+
+    // All the filters are ranges, so they share the same prompt format, and
+    // zero is once again returned when a side is left blank
+    fn get_range(prompt: &[&str]) -> (f64, f64) {
+        loop {
+            let input = input(prompt);
+
+            if input.is_empty() {
+                break (0.0, 0.0);
+            }
+
+            let bounds: Vec<&str> = input.split('-').map(|bound| bound.trim()).collect();
+
+            if bounds.len() != 2 {
+                println!("\nInvalid input! Please write the range as `MIN-MAX`.");
+                continue;
+            }
+
+            let min = if bounds[0].is_empty() {
+                Ok(0.0)
+            } else {
+                bounds[0].parse::<f64>()
+            };
+
+            let max = if bounds[1].is_empty() {
+                Ok(0.0)
+            } else {
+                bounds[1].parse::<f64>()
+            };
+
+            match (min, max) {
+                (Ok(min), Ok(max)) if min >= 0.0 && max >= 0.0 => {
+                    if max > 0.0 && min > max {
+                        println!("\nThe minimum can't be larger than the maximum!");
+                        continue;
+                    }
+
+                    break (min, max);
+                }
+                _ => {
+                    println!("\nInvalid input!");
+                    println!("Make sure both sides of the range are positive numbers.");
+                    continue;
+                }
+            }
+        }
+    }
+
+    fn default_path() -> String {
+        // This was the most obvious way to create a folder in the
+        // current directory, and I'll probably keep it this way as it
+        // works and I'm too dumb to figure out a more idiomatic method
+        let path = format!(
+            "{}/Discord Images",
+            current_dir()
+                .expect("Failed to get current directory!")
+                .to_str()
+                .expect("Failed to get current directory!")
+        );
+
+        create_dir_all(&path).expect("Failed to create directory!");
+
+        path
+    }
+
+    // Downloads normally go forward from the Start Date, but going backwards
+    // from the newest message means a limit only needs the most recent pages.
+    // A random order looks at the whole range and then picks a sample as big
+    // as the limit, which the seed makes reproducible, while ranking by
+    // reactions keeps the most reacted images, optionally only counting one
+    // emoji.
+    #[derive(Clone, Debug, PartialEq)]
+    pub enum Order {
+        Oldest,
+        Newest,
+        Random(u64),
+        Reactions(Option<String>),
+    }
+
+    // The program can either download a channel's history once, or only its
+    // pinned messages, keep watching it for new images, or run as a bot that
+    // downloads channels on request
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub enum Mode {
+        Download,
+        Pins,
+        Watch,
+        Bot,
+    }
+
+    // The resoning behind the types used in the `struct` were all mentioned in
+    // the functions that generate them, so I won't repeat them
+    #[derive(Clone)]
+    pub struct Config {
+        pub token: String,
+        pub channel: String,
+        // Other channels picked along with `channel`, which are downloaded
+        // one after another, or all watched at once
+        pub channels: Vec<String>,
+        pub mode: Mode,
+        pub date: u64,
+        // Messages can also be used as anchors, with only the ones sent
+        // before `until`, or the page `around` a message, being downloaded.
+        // Just like `date`, `0` means they aren't used.
+        pub until: u64,
+        pub around: u64,
+        pub quantity: u32,
+        // The most images to take from a single message or author, where `0`
+        // means there's no limit, just like `quantity`
+        pub per_message: u32,
+        pub per_author: u32,
+        pub order: Order,
+        pub path: String,
+        pub filter: filter::Filter,
+        pub convert: Option<convert::Options>,
+        pub strip_metadata: bool,
+        pub thumbnails: Option<thumbnail::Options>,
+        pub collage: Option<collage::Options>,
+        // An `index.html` page to browse the saved images with
+        pub gallery: bool,
+        pub archive: Option<archive::Options>,
+        pub post: Option<post::Options>,
+        // If this is set, the matching images are only listed, and written to
+        // `export` if it's set too
+        pub dry_run: bool,
+        pub export: Option<PathBuf>,
+        pub endpoints: endpoint::Endpoints,
+        // Every request is sent through this client, which is cheap to clone
+        // as clones share the same connections
+        pub client: Client,
+    }
+
+    impl Config {
+        // When several channels were picked, each one is downloaded with its
+        // own copy of the settings, into its own folder just like the bot's
+        // downloads, so that their collages don't overwrite each other. The
+        // exported lists are kept where they were asked for, but with the
+        // channel's ID added to their name, such as `images-1234.csv`.
+        pub fn split(&self) -> Vec<Config> {
+            if self.channels.is_empty() {
+                return vec![self.clone()];
+            }
+
+            std::iter::once(&self.channel)
+                .chain(self.channels.iter())
+                .map(|channel| {
+                    let mut selected = self.clone();
+                    selected.channel = channel.clone();
+                    selected.channels = Vec::new();
+                    selected.path = Path::new(&self.path)
+                        .join(channel)
+                        .to_string_lossy()
+                        .to_string();
+                    selected.export = self.export.as_ref().map(|file| {
+                        let mut name = file.file_stem().unwrap_or_default().to_os_string();
+                        name.push(format!("-{}", channel));
+
+                        if let Some(ext) = file.extension() {
+                            name.push(".");
+                            name.push(ext);
+                        }
+
+                        file.with_file_name(name)
+                    });
+                    selected
+                })
+                .collect()
+        }
+    }
+}
+
+pub mod run {
+    use {
+        super::*,
+        downloader::DownloadReport,
+        progress::{Event, Reason},
+        rand::{seq::index, SeedableRng},
+        rand_chacha::ChaCha8Rng,
+        serenity::model::channel::{Message, ReactionType},
+        std::{
+            cmp::Reverse,
+            collections::{HashMap, HashSet},
+            fs::{create_dir_all, remove_file, write},
+            path::{Path, PathBuf},
+        },
+    };
+
+    // Jut like in the `config` module, the `all()` function calls its own
+    // functions and handles the whole task. I could have simply written two
+    // functions, `config()` and `run()`, outside of modules, but I only
+    // intended for them to be used, and as far as I know hey wouldn't be able
+    // to use private functions from the modules otherwise.
+    //
+    // The download itself is done by `download()`, which doesn't print
+    // anything so that it can also be used as a library through the
+    // `Downloader`, while this function displays its results in the terminal.
+    pub async fn all(selected: config::Config) -> error::Result<DownloadReport> {
+        // This prints an empty sline to separate he download messages from the
+        // user's last input, once again for cosmetic reasons (this is a tool
+        // for Sneaker Twitter Designers, after all)
+        println!();
+
+        // Each saved image is listed above the progress bar to showcase the
+        // program's speed
+        let bar = progress::Bar::new();
+
+        // Errors that stop the download are returned for `main()` to print
+        let report = download(&selected, &|event| bar.handle(event)).await;
+        bar.finish();
+        let report = report?;
+
+        for err in &report.errors {
+            println!("{}", err);
+        }
+
+        // The seed is shown first so that the same sample can be picked again
+        if let config::Order::Random(seed) = selected.order {
+            println!("Picked the images using the seed {}.", seed);
+        }
+
+        // A dry run lists every matching image instead of the usual results
+        if selected.dry_run {
+            for m in &report.matched {
+                println!("- {} ({}, {})", m.url, listing::size(m.size), m.author);
+            }
+
+            println!(
+                "\nFound {} matching image{}, {} in total!",
+                report.matched.len(),
+                if report.matched.len() == 1 { "" } else { "s" },
+                listing::size(report.bytes())
+            );
+
+            if let Some(file) = &selected.export {
+                if file.exists() {
+                    println!("The list was exported to {}!", file.display());
+                }
+            }
+
+            return Ok(report);
+        }
+
+        // Conditional statements are used to customize the final message
+        if report.saved.is_empty() {
+            println!(
+                "The channel doesn't contain any images{}!",
+                if selected.date == 0 {
+                    ""
+                } else {
+                    " in the selected time range"
+                }
+            );
+        } else {
+            println!(
+                "\nSuccessfully saved {} image{}!",
+                report.saved.len(),
+                if report.saved.len() == 1 { "" } else { "s" }
+            );
+
+            if !report.located.is_empty() {
+                println!(
+                    "\nRemoved location data from {} image{}:",
+                    report.located.len(),
+                    if report.located.len() == 1 { "" } else { "s" }
+                );
+
+                for file in &report.located {
+                    println!("- {}", file.display());
+                }
+            }
+        }
+
+        if let Some(file) = &report.collage {
+            println!("\nSaved the collage as {}!", file.display());
+        }
+
+        if let Some(file) = &report.gallery {
+            println!("\nSaved the gallery as {}!", file.display());
+        }
+
+        if let Some(file) = &report.archive {
+            println!("\nSaved the archive as {}!", file.display());
+        }
+
+        match &report.posted {
+            Some(Ok(_)) => println!("\nPosted the results on Discord!"),
+            Some(Err(err)) => println!("\nFailed to post the results: {}", err),
+            None => {}
+        }
+
+        Ok(report)
+    }
+
+    // Failing to request the channel's messages stops the download, as there
+    // would be nothing left to do, while problems with single images are only
+    // listed in the report's `errors`. Everything that happens is also sent to
+    // `progress` as it happens.
+    pub(crate) async fn download(
+        selected: &config::Config,
+        progress: &(dyn Fn(Event) + Send + Sync),
+    ) -> error::Result<DownloadReport> {
+        // Since the path was saved as a String, as I explained earlier, the
+        // real path has to be created here
+        let path = Path::new(&selected.path);
+
+        let mut report = DownloadReport {
+            channel: selected.channel.clone(),
+            matched: Vec::new(),
+            saved: Vec::new(),
+            located: Vec::new(),
+            collage: None,
+            gallery: None,
+            archive: None,
+            posted: None,
+            errors: Vec::new(),
+        };
+
+        // The folder is usually created by the prompts, but not when the
+        // settings come from the `Downloader`
+        create_dir_all(path)?;
+
+        // The number of images selected so far, which is compared to the
+        // limit, along with the number selected from each author, so that one
+        // person posting a lot can't take up the whole limit
+        let mut count: usize = 0;
+        let mut authors: HashMap<u64, u32> = HashMap::new();
+
+        // The IDs of the selected attachments, ensuring that images aren't
+        // downloaded more than once
+        let mut seen: HashSet<u64> = HashSet::new();
+
+        // Random samples and rankings need every candidate in the range, which
+        // are kept here, along with their reactions, until the last page,
+        // ignoring the limit and the caps until then, so that the caps keep
+        // the sampled or most reacted images rather than the first ones
+        let pooled = matches!(
+            selected.order,
+            config::Order::Random(_) | config::Order::Reactions(_)
+        );
+        let mut pool = Vec::new();
+
+        // The saved images are listed in the gallery once they're all done
+        let mut entries = Vec::new();
+
+        // If an archive was selected, it's created before the downloads start
+        // so that the images can be written to it as they're saved
+        let mut archive = match selected.archive.as_ref().filter(|_| !selected.dry_run) {
+            Some(options) => match archive::Writer::create(path, options.kind) {
+                Ok(writer) => Some(writer),
+                Err(err) => {
+                    report
+                        .errors
+                        .push(format!("Failed to create the archive: {}", err));
+                    None
+                }
+            },
+            None => None,
+        };
+
+        // When going forward, `after` is initialized as the Start Date, as all
+        // images should have be sent after it (even if it's `0`, as otherwise
+        // Discord would return the newest messages), while going backwards
+        // starts from the newest message, without a cursor, unless there's an
+        // end
+        let mut cursor = match selected.order {
+            config::Order::Newest if selected.until > 0 => {
+                Some(format!("before={}", selected.until))
+            }
+            config::Order::Newest => None,
+            _ => Some(format!("after={}", selected.date)),
+        };
+
+        // Pins and the messages around another one are a single page
+        let pins = selected.mode == config::Mode::Pins;
+        let single = pins || selected.around > 0;
+        let within = |msg: &Message| {
+            msg.id.0 > selected.date && (selected.until == 0 || msg.id.0 < selected.until)
+        };
+
+        loop {
+            // After getting migraines due to my completely unnecessary efforts to
+            // serialize Discord's Message API JSON responses, I decided to simply
+            // use the ones defined in the `serenity` crate, importing their
+            // `Message` struct (and making me cry for wasting so much time)
+            //
+            // Pinned messages all come at once, so there's only one "page",
+            // which can include messages from outside the range since they're
+            // sorted by when they were pinned
+            let mut res: Vec<Message> = if pins {
+                get_pins(selected).await?
+            } else if selected.around > 0 {
+                get(selected, Some(&format!("around={}", selected.around))).await?
+            } else {
+                get(selected, cursor.as_deref()).await?
+            };
+
+            if single {
+                res.retain(within);
+            }
+
+            // Once all messages are requested, there will be no new ones and
+            // the program will be done
+            if res.is_empty() {
+                break;
+            } else {
+                // Discord always sorts pages from newest to oldest, whichever
+                // way they're requested, so they're sorted in the selected
+                // order, which also makes the last message the next cursor
+                res.sort_by_key(|msg| msg.id.0);
+
+                if selected.order == config::Order::Newest {
+                    res.reverse();
+                }
+
+                let last = res[res.len() - 1].id.0;
+
+                cursor = Some(match selected.order {
+                    config::Order::Newest => format!("before={}", last),
+                    _ => format!("after={}", last),
+                });
+
+                // Once the limit is reached, or the Start Date is reached while
+                // going backwards, no more pages are needed
+                let mut done = false;
+
+                progress(Event::PageFetched {
+                    messages: res.len(),
+                });
+
+                // The page's images are selected before any of them are
+                // downloaded, so that the progress bar knows how much is left
+                let mut queue = Vec::new();
+
+                // Since the API's response is simply an array of messages, I iterate
+                // through each one
+                for msg in res {
+                    // Going forward, the end is reached once a message is
+                    // sent after it, and going backwards, once one is sent
+                    // before the Start Date
+                    if !within(&msg) {
+                        done = true;
+                        break;
+                    }
+
+                    // Messages whose text doesn't pass the keyword filters
+                    // are skipped without looking at their attatchments
+                    let matches = selected.filter.message(&msg);
+
+                    // The number of images taken from this message so far
+                    let mut taken = 0;
+
+                    let reactions = match &selected.order {
+                        config::Order::Reactions(emoji) => reactions(&msg, emoji.as_deref()),
+                        _ => 0,
+                    };
+
+                    // Not all messages have attatchments, but not all attatchments are
+                    // images either, so each one must be checked
+                    for att in msg.attachments.iter() {
+                        let posted = authors.get(&msg.author.id.0).copied().unwrap_or(0);
+
+                        // This checks that the attatchment is an image by checking
+                        // if a `width` property is specified, and then makes
+                        // sure it passes the selected size filters and caps
+                        let reason = if att.width.is_none() {
+                            Some(Reason::NotImage)
+                        } else if !matches {
+                            Some(Reason::Message)
+                        } else if !selected.filter.attachment(att) {
+                            Some(Reason::Filtered)
+                        } else if !pooled
+                            && selected.per_message > 0
+                            && taken >= selected.per_message
+                        {
+                            Some(Reason::MessageLimit)
+                        } else if !pooled
+                            && selected.per_author > 0
+                            && posted >= selected.per_author
+                        {
+                            Some(Reason::AuthorLimit)
+                        } else {
+                            None
+                        };
+
+                        if let Some(reason) = reason {
+                            progress(Event::Skipped {
+                                url: att.url.clone(),
+                                reason,
+                            });
+                            continue;
+                        }
+
+                        // Images are only downloaded once, even if Discord
+                        // somehow returned the same message twice
+                        if !seen.insert(*att.id.as_u64()) {
+                            continue;
+                        }
+
+                        let found = listing::Match::new(&msg, att);
+
+                        if pooled {
+                            pool.push(Candidate {
+                                image: found,
+                                author: msg.author.id.0,
+                                reactions,
+                            });
+                        } else {
+                            progress(Event::Queued {
+                                url: att.url.clone(),
+                                size: att.size,
+                            });
+
+                            report.matched.push(found.clone());
+                            queue.push(found);
+                        }
+
+                        count += 1;
+                        taken += 1;
+                        authors.insert(msg.author.id.0, posted + 1);
+
+                        // The limit counts images, not messages, so it's
+                        // checked after each one, stopping right away instead
+                        // of waiting for another image that might never come
+                        if !pooled && selected.quantity > 0 && selected.quantity as usize <= count {
+                            done = true;
+                            break;
+                        }
+                    }
+
+                    if done {
+                        break;
+                    }
+                }
+
+                // A dry run doesn't download anything, as the matching images
+                // have already been listed, and samples and rankings can only
+                // be picked once every page has been seen
+                if !selected.dry_run && !pooled {
+                    fetch(
+                        queue,
+                        selected,
+                        &mut report,
+                        &mut archive,
+                        &mut entries,
+                        progress,
+                    )
+                    .await;
+                }
+
+                if done || single {
+                    break;
+                }
+            }
+        }
+
+        // Once every candidate is known, the sample or the most reacted images
+        // are picked and downloaded
+        if pooled {
+            let picked = match &selected.order {
+                config::Order::Random(seed) => sample(pool, *seed, selected, progress),
+                _ => top(pool, selected, progress),
+            };
+            let mut queue = Vec::new();
+
+            for Candidate { image: found, .. } in picked {
+                progress(Event::Queued {
+                    url: found.url.clone(),
+                    size: found.size,
+                });
+
+                report.matched.push(found.clone());
+                queue.push(found);
+            }
+
+            if !selected.dry_run {
+                fetch(
+                    queue,
+                    selected,
+                    &mut report,
+                    &mut archive,
+                    &mut entries,
+                    progress,
+                )
+                .await;
+            }
+        }
+
+        if let Some(file) = &selected.export {
+            if let Err(err) = listing::export(&report.matched, file) {
+                report
+                    .errors
+                    .push(format!("Failed to export {}: {}", file.display(), err));
+            }
+        }
+
+        // Nothing was saved during a dry run, so there's nothing else to do
+        if selected.dry_run {
+            return Ok(report);
+        }
+
+        // The collage is made last, as it needs every image to be saved
+        if let Some(options) = &selected.collage {
+            if !report.saved.is_empty() {
+                match collage::save(&report.saved, options, path) {
+                    Ok(file) => report.collage = Some(file),
+                    Err(err) => report
+                        .errors
+                        .push(format!("Failed to make the collage: {}", err)),
+                }
+            }
+        }
+
+        // The gallery links to the images and thumbnails, so it's saved in
+        // the same folder, and added to the archive along with them
+        if selected.gallery && !entries.is_empty() {
+            match gallery::save(&entries, path, guild(selected).await) {
+                Ok(file) => report.gallery = Some(file),
+                Err(err) => report
+                    .errors
+                    .push(format!("Failed to make the gallery: {}", err)),
+            }
+        }
+
+        // The archive is finished once everything else has been added to it,
+        // but the files are only removed once they've been posted
+        let mut added = Vec::new();
+
+        if let Some(mut writer) = archive {
+            if let Some(file) = &report.collage {
+                if let Err(err) = add(&mut writer, file, "") {
+                    report
+                        .errors
+                        .push(format!("Failed to archive the collage: {}", err));
+                }
+            }
+
+            if let Some(file) = &report.gallery {
+                if let Err(err) = add(&mut writer, file, "") {
+                    report
+                        .errors
+                        .push(format!("Failed to archive the gallery: {}", err));
+                }
+            }
+
+            match writer.finish() {
+                Ok((file, files)) => {
+                    report.archive = Some(file);
+                    added = files;
+                }
+                Err(err) => report
+                    .errors
+                    .push(format!("Failed to finish the archive: {}", err)),
+            }
+        }
+
+        // The results are posted last, so that the collage and archive can be
+        // uploaded along with them
+        if let Some(options) = &selected.post {
+            let files: Vec<PathBuf> = report
+                .collage
+                .iter()
+                .chain(report.archive.iter())
+                .cloned()
+                .collect();
+
+            report.posted = Some(
+                post::send(
+                    &selected.client,
+                    &selected.endpoints,
+                    &selected.token,
+                    options,
+                    &report.describe(),
+                    &files,
+                )
+                .await,
+            );
+        }
+
+        // Finally, if the user only wanted the archive, the files that were
+        // written to it are removed from the image directory. The collage is
+        // kept, as it's a single file that's meant to be looked at or shared,
+        // while the gallery is useless without the images it links to, so it's
+        // only left in the archive.
+        if let Some(options) = &selected.archive {
+            if !options.keep_folder {
+                for file in added.iter() {
+                    if report.collage.as_ref() == Some(file) {
+                        continue;
+                    }
+
+                    if let Err(err) = remove_file(file) {
+                        report
+                            .errors
+                            .push(format!("Failed to remove {}: {}", file.display(), err));
+                    } else if report.gallery.as_ref() == Some(file) {
+                        report.gallery = None;
+                    }
+                }
+            }
+        }
+
+        Ok(report)
+    }
+
+    // Every queued image is saved and processed, and then added to the report
+    // and to the archive, if there is one
+    async fn fetch(
+        queue: Vec<listing::Match>,
+        selected: &config::Config,
+        report: &mut DownloadReport,
+        archive: &mut Option<archive::Writer>,
+        entries: &mut Vec<gallery::Entry>,
+        progress: &(dyn Fn(Event) + Send + Sync),
+    ) {
+        for found in queue {
+            // The image is saved and processed using the `process()`
+            // function, defined below
+            let image = match process(&found.url, selected).await {
+                Ok(image) => image,
+                Err(err) => {
+                    report
+                        .errors
+                        .push(format!("Failed to save {}: {}", found.url, err));
+                    progress(Event::Failed {
+                        url: found.url,
+                        size: found.size,
+                        error: err.to_string(),
+                    });
+                    continue;
+                }
+            };
+
+            progress(Event::Saved {
+                url: found.url.clone(),
+                file: image.file.clone(),
+                bytes: image.bytes,
+            });
+
+            report.errors.extend(image.errors.iter().cloned());
+
+            if image.location {
+                report.located.push(image.file.clone());
+            }
+
+            // The image is added to the archive right away, along with
+            // its thumbnail, using the same layout as the image
+            // directory
+            if let Some(writer) = archive {
+                if let Err(err) =
+                    add(writer, &image.file, "").and_then(|_| match &image.thumbnail {
+                        Some(thumb) => add(writer, thumb, thumbnail::FOLDER),
+                        None => Ok(()),
+                    })
+                {
+                    report
+                        .errors
+                        .push(format!("Failed to archive the image: {}", err));
+                }
+            }
+
+            if selected.gallery {
+                entries.push(gallery::Entry {
+                    file: image.file.clone(),
+                    thumbnail: image.thumbnail.clone(),
+                    image: found,
+                });
+            }
+
+            report.saved.push(image.file);
+        }
+    }
+
+    // The sample is picked with ChaCha, whose output is the same on every
+    // platform and version, so the same seed always picks the same images.
+    // The whole pool is shuffled and walked through until the limit, skipping
+    // the images over the caps, and the picked ones are then put back in the
+    // order they were posted in.
+    fn sample(
+        pool: Vec<Candidate>,
+        seed: u64,
+        selected: &config::Config,
+        progress: &(dyn Fn(Event) + Send + Sync),
+    ) -> Vec<Candidate> {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let shuffled = index::sample(&mut rng, pool.len(), pool.len()).into_vec();
+
+        let mut pool: Vec<Option<Candidate>> = pool.into_iter().map(Some).collect();
+        let shuffled = shuffled
+            .into_iter()
+            .filter_map(|i| pool[i].take())
+            .collect();
+
+        let mut picked = capped(shuffled, selected, progress);
+        picked.sort_by_key(|candidate| (candidate.image.message, candidate.image.id));
+
+        picked
+    }
+
+    // An image waiting to be sampled or ranked, along with what the caps and
+    // the ranking need to know about its message
+    struct Candidate {
+        image: listing::Match,
+        author: u64,
+        reactions: u64,
+    }
+
+    // The images are ranked from the most to the least reacted, and those
+    // with the same number of reactions are kept in the order they were
+    // posted in
+    fn top(
+        mut pool: Vec<Candidate>,
+        selected: &config::Config,
+        progress: &(dyn Fn(Event) + Send + Sync),
+    ) -> Vec<Candidate> {
+        pool.sort_by_key(|candidate| Reverse(candidate.reactions));
+
+        capped(pool, selected, progress)
+    }
+
+    // The caps are applied while walking through the candidates in the order
+    // they're picked in, so that the images an author or a message is limited
+    // to are the picked ones, rather than the first ones they posted
+    fn capped(
+        candidates: Vec<Candidate>,
+        selected: &config::Config,
+        progress: &(dyn Fn(Event) + Send + Sync),
+    ) -> Vec<Candidate> {
+        let mut messages: HashMap<u64, u32> = HashMap::new();
+        let mut authors: HashMap<u64, u32> = HashMap::new();
+        let mut picked = Vec::new();
+
+        for candidate in candidates {
+            if selected.quantity > 0 && picked.len() >= selected.quantity as usize {
+                break;
+            }
+
+            let taken = messages.entry(candidate.image.message).or_insert(0);
+            let posted = authors.entry(candidate.author).or_insert(0);
+
+            let reason = if selected.per_message > 0 && *taken >= selected.per_message {
+                Some(Reason::MessageLimit)
+            } else if selected.per_author > 0 && *posted >= selected.per_author {
+                Some(Reason::AuthorLimit)
+            } else {
+                None
+            };
+
+            if let Some(reason) = reason {
+                progress(Event::Skipped {
+                    url: candidate.image.url.clone(),
+                    reason,
+                });
+                continue;
+            }
+
+            *taken += 1;
+            *posted += 1;
+            picked.push(candidate);
+        }
+
+        picked
+    }
+
+    // Without an emoji, every reaction on the message is counted. Otherwise,
+    // only the ones using it are, which can be either a regular emoji, such as
+    // 🔥, or a custom one, written as its name, its ID or `<:name:id>`.
+    fn reactions(msg: &Message, emoji: Option<&str>) -> u64 {
+        let emoji = emoji.map(|emoji| {
+            emoji
+                .trim()
+                .trim_start_matches("<a:")
+                .trim_start_matches("<:")
+                .trim_end_matches('>')
+                .trim_matches(':')
+                .to_string()
+        });
+
+        msg.reactions
+            .iter()
+            .filter(|reaction| match &emoji {
+                None => true,
+                Some(emoji) => match &reaction.reaction_type {
+                    ReactionType::Unicode(name) => name == emoji,
+                    ReactionType::Custom { id, name, .. } => {
+                        let id = id.as_u64().to_string();
+
+                        // `name:id` only matches if both parts do
+                        match emoji.split_once(':') {
+                            Some((n, i)) => name.as_deref() == Some(n) && i == id,
+                            None => name.as_deref() == Some(emoji.as_str()) || *emoji == id,
+                        }
+                    }
+                    _ => false,
+                },
+            })
+            .map(|reaction| reaction.count)
+            .sum()
+    }
+
+    // Files are stored in the archive under their name, inside `folder` if
+    // one is given
+    fn add(writer: &mut archive::Writer, file: &Path, folder: &str) -> std::io::Result<()> {
+        let name = file
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        if folder.is_empty() {
+            writer.add(file, &name)
+        } else {
+            writer.add(file, &format!("{}/{}", folder, name))
+        }
+    }
+
+    // Every image goes through the same steps once it's been selected, whether
+    // it was found in the channel's history or posted while the channel was
+    // being watched, so they're all handled here
+    pub(crate) async fn process(url: &str, selected: &config::Config) -> error::Result<Processed> {
+        let (mut file, bytes) = save(
+            &selected.client,
+            url,
+            &selected.endpoints.cdn(url),
+            Path::new(&selected.path),
+        )
+        .await?;
+        let mut location = false;
+        let mut thumb = None;
+        let mut errors = Vec::new();
+
+        // The metadata is removed from the downloaded file before anything
+        // else, as converting it would drop the location without it being
+        // reported, and a kept original would still contain it
+        if selected.strip_metadata {
+            match metadata::strip(&file) {
+                Ok(report) => location = report.location,
+                Err(err) => errors.push(format!(
+                    "Failed to remove the metadata from {}: {}",
+                    file.display(),
+                    err
+                )),
+            }
+        }
+
+        // Images are then converted before anything else uses them, so that
+        // thumbnails and collages are made from the final files
+        if let Some(options) = &selected.convert {
+            match convert::normalize(&file, options) {
+                Ok(converted) => file = converted,
+                Err(err) => errors.push(format!("Failed to convert {}: {}", file.display(), err)),
+            }
+        }
+
+        // Thumbnails are made right away, so that they can be browsed while
+        // the rest are downloading
+        if let Some(options) = &selected.thumbnails {
+            match thumbnail::create(&file, options) {
+                Ok((path, _)) => thumb = Some(path),
+                Err(err) => errors.push(format!(
+                    "Failed to make a thumbnail of {}: {}",
+                    file.display(),
+                    err
+                )),
+            }
+        }
+
+        Ok(Processed {
+            file,
+            bytes,
+            thumbnail: thumb,
+            location,
+            errors,
+        })
+    }
+
+    pub(crate) struct Processed {
+        pub file: PathBuf,
+        // The size of the downloaded image, before it was converted
+        pub bytes: u64,
+        pub thumbnail: Option<PathBuf>,
+        // Whether the image contained location data
+        pub location: bool,
+        pub errors: Vec<String>,
+    }
+
+    // Messages don't say which server they were sent in, so it's requested
+    // from the channel, which doesn't have one if it's a direct message. The
+    // gallery still works without it, so errors are ignored.
+    async fn guild(selected: &config::Config) -> Option<u64> {
+        let res = config::api(
+            &selected.client,
+            &selected.endpoints,
+            &selected.token,
+            &format!("channels/{}", selected.channel),
+        )
+        .await
+        .ok()?;
+
+        res.json::<serde_json::Value>().await.ok()?["guild_id"]
+            .as_str()?
+            .parse()
+            .ok()
+    }
+
+    // `cursor` is either `after={id}` or `before={id}`, and the newest
+    // messages are returned without one
+    async fn get(selected: &config::Config, cursor: Option<&str>) -> error::Result<Vec<Message>> {
+        // The API is extremely simple, as shown below
+        let mut url = selected
+            .endpoints
+            .api(&format!("channels/{}/messages?limit=100", selected.channel));
+
+        if let Some(cursor) = cursor {
+            url = format!("{}&{}", url, cursor);
+        }
+
+        request(selected, url).await
+    }
+
+    // Discord returns every pinned message in one response, from the most to
+    // the least recently pinned
+    async fn get_pins(selected: &config::Config) -> error::Result<Vec<Message>> {
+        let url = selected
+            .endpoints
+            .api(&format!("channels/{}/pins", selected.channel));
+
+        request(selected, url).await
+    }
+
+    async fn request(selected: &config::Config, url: String) -> error::Result<Vec<Message>> {
+        // The authorization in the API is as basic as adding a header with the
+        // Bot Token, formatted this way
+        let auth = format!("Bot {}", selected.token);
+        let res = selected
+            .client
+            .get(url)
+            .header("Authorization", auth)
+            .send()
+            .await?;
+
+        // The response is serialized as a `Vec<Message>` as explained in the
+        // `all()` function, once it's been checked for errors
+        Ok(error::check(res).await?.json::<Vec<Message>>().await?)
+    }
+
+    // The image is named after the `url` Discord sent, but downloaded from
+    // `source`, which is the same URL on the selected CDN. The number of bytes
+    // that were downloaded is returned along with the path.
+    async fn save(
+        client: &reqwest::Client,
+        url: &str,
+        source: &str,
+        path: &Path,
+    ) -> error::Result<(PathBuf, u64)> {
+        let invalid = || error::Error::Parse(format!("Invalid image URL: {}", url));
+
+        // Attachment URLs look like `/attachments/{channel}/{id}/{filename}`,
+        // followed by a signed query string (`?ex=…&is=…&hm=…`) which isn't
+        // part of the file's name, so only the path is looked at
+        let parsed = reqwest::Url::parse(url).map_err(|_| invalid())?;
+        let segments: Vec<&str> = parsed.path_segments().ok_or_else(invalid)?.collect();
+
+        // Although the Attachment ID is specified in the `Attachment` `struct`,
+        // it's easier to extract it from the Image URL
+        let name = segments.get(2).ok_or_else(invalid)?;
+
+        // The filetype is taken from the attachment's original file name
+        let ext = segments
+            .last()
+            .and_then(|file| Path::new(file).extension())
+            .and_then(|ext| ext.to_str())
+            .ok_or_else(invalid)?;
 
         // The image's file name is formed using those two values
         let path = path.join(format!("{}.{}", name, ext));
 
-        // The image is then downloaded by reqeuesting its URL
-        let img = reqwest::get(url)
-            .await
-            .expect("Failed to GET image!")
-            // It's then converted to bytes so it can be written on the new file
-            .bytes()
-            .await
-            .expect("Gailed to convert image to bytes!");
+        // The image is then downloaded by reqeuesting its URL, and converted
+        // to bytes so it can be written on the new file
+        let res = error::check(client.get(source).send().await?).await?;
+        let img = res.bytes().await?;
+
+        let bytes = img.len() as u64;
 
         // And saved to storage
-        write(path, img).expect("Failed to save image!");
+        write(&path, img)?;
 
-        // The task's completion is then logged to display the program's
-        // progress and showcase it's speed
-        println!("Saved {}.{}!", name, ext);
+        Ok((path, bytes))
     }
 
     // This function allows for unrecoverable errors to be displayed to the user
@@ -0,0 +1,96 @@
+use {
+    serde::Serialize,
+    serenity::model::channel::{Attachment, Message},
+    std::{fs::write, io, path::Path},
+};
+
+// Every attachment that passes the filters is recorded, whether it ends up
+// being downloaded or the run is only a dry run, so that the list can be
+// printed or exported before pulling thousands of images
+#[derive(Clone, Debug, Serialize)]
+pub struct Match {
+    pub id: u64,
+    pub message: u64,
+    pub channel: u64,
+    pub author: String,
+    // The date the message was sent, in RFC 3339 format
+    pub date: String,
+    pub filename: String,
+    pub url: String,
+    pub size: u64,
+    pub width: u64,
+    pub height: u64,
+}
+
+impl Match {
+    pub fn new(msg: &Message, att: &Attachment) -> Match {
+        Match {
+            id: *att.id.as_u64(),
+            message: msg.id.0,
+            channel: msg.channel_id.0,
+            author: msg.author.name.clone(),
+            date: msg.timestamp.to_rfc3339(),
+            filename: att.filename.clone(),
+            url: att.url.clone(),
+            size: att.size,
+            width: att.width.unwrap_or(0),
+            height: att.height.unwrap_or(0),
+        }
+    }
+}
+
+// The list is written as JSON if the file ends in `.json`, and as CSV, which
+// can be opened in any spreadsheet program, otherwise
+pub fn export(matches: &[Match], path: &Path) -> io::Result<()> {
+    let json = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+
+    let content = if json {
+        serde_json::to_string_pretty(matches)?
+    } else {
+        let mut csv =
+            String::from("id,message,channel,author,date,filename,url,size,width,height\n");
+
+        for m in matches {
+            csv.push_str(&format!(
+                "{},{},{},{},{},{},{},{},{},{}\n",
+                m.id,
+                m.message,
+                m.channel,
+                escape(&m.author),
+                m.date,
+                escape(&m.filename),
+                escape(&m.url),
+                m.size,
+                m.width,
+                m.height
+            ));
+        }
+
+        csv
+    };
+
+    write(path, content)
+}
+
+// Fields containing commas, quotes or line breaks have to be quoted, with
+// quotes inside them doubled
+fn escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+// Sizes are shown in the most readable unit
+pub fn size(bytes: u64) -> String {
+    if bytes >= 1024 * 1024 * 1024 {
+        format!("{:.2} GB", bytes as f64 / 1024.0 / 1024.0 / 1024.0)
+    } else if bytes >= 1024 * 1024 {
+        format!("{:.1} MB", bytes as f64 / 1024.0 / 1024.0)
+    } else {
+        format!("{:.1} KB", bytes as f64 / 1024.0)
+    }
+}
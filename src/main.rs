@@ -1,4 +1,8 @@
-use discord_image_downloader::{config, run};
+use discord_image_downloader::{
+    bot,
+    config::{self, Mode},
+    run, watch,
+};
 use std::io;
 
 #[tokio::main]
@@ -6,7 +10,32 @@ async fn main() {
     println!("Discord Image Downloader");
     println!("Made by Subreme :)");
 
-    run::all(config::all().await).await;
+    let selected = config::all().await;
+
+    // Watching a channel or running the bot only ends if the connection to
+    // Discord is lost, in which case the program closes just like after a
+    // download
+    match selected.mode {
+        Mode::Download | Mode::Pins => {
+            let several = !selected.channels.is_empty();
+
+            // A failed channel doesn't stop the others from being downloaded
+            for selected in selected.split() {
+                if several {
+                    println!(
+                        "\nDownloading the images in channel {}...",
+                        selected.channel
+                    );
+                }
+
+                if let Err(err) = run::all(selected).await {
+                    println!("\nFailed to download the images: {}", err);
+                }
+            }
+        }
+        Mode::Watch => watch::all(selected).await,
+        Mode::Bot => bot::all(selected).await,
+    }
 
     println!("\nMake sure to star https://github.com/subreme/discord-image-downloader if you found this useful!");
     println!("\nHit `Enter` to close!");
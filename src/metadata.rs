@@ -0,0 +1,379 @@
+use {
+    flate2::Crc,
+    image::DynamicImage,
+    std::{
+        fs::{read, write},
+        io,
+        path::{Path, PathBuf},
+    },
+};
+
+// Phone photos often contain EXIF data with the exact location they were taken
+// at, which shouldn't end up in a collage that's posted publicly, so this
+// module removes it (along with XMP, IPTC and ICC data) from saved images.
+//
+// Re-encoding the images with the `image` crate would also drop it, however
+// that would lose quality for JPEGs, so the metadata is instead cut out of the
+// files directly, leaving the pixels untouched.
+//
+// The only tag that's kept is the orientation, since phones save portrait
+// photos sideways and rely on it to show them the right way up, so a tiny EXIF
+// block with only that tag replaces the original one when it's needed.
+
+pub struct Report {
+    pub path: PathBuf,
+    // Whether the file had GPS coordinates in its EXIF or XMP data
+    pub location: bool,
+    // Whether anything was removed at all
+    pub stripped: bool,
+}
+
+pub fn strip(path: &Path) -> io::Result<Report> {
+    let data = read(path)?;
+    let result = parse(&data);
+
+    let mut report = Report {
+        path: path.to_path_buf(),
+        location: false,
+        stripped: false,
+    };
+
+    if let Some((stripped, exif)) = result {
+        report.location = exif.location;
+
+        if stripped != data {
+            write(path, stripped)?;
+            report.stripped = true;
+        }
+    }
+
+    Ok(report)
+}
+
+// The format is detected from the file's signature, and formats that don't
+// store metadata (or that aren't supported) are left untouched
+fn parse(data: &[u8]) -> Option<(Vec<u8>, Exif)> {
+    if data.starts_with(&[0xFF, 0xD8]) {
+        jpeg(data)
+    } else if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+        png(data)
+    } else if data.len() >= 12 && &data[0..4] == b"RIFF" && &data[8..12] == b"WEBP" {
+        webp(data)
+    } else {
+        None
+    }
+}
+
+// The `image` crate doesn't rotate images by their EXIF orientation, so the
+// collage and conversions read it here, where `None` means the image is
+// already upright
+pub fn orientation(path: &Path) -> Option<u16> {
+    parse(&read(path).ok()?)?.1.orientation
+}
+
+// Each orientation is a combination of a rotation and a mirror
+pub fn upright(img: DynamicImage, orientation: Option<u16>) -> DynamicImage {
+    match orientation {
+        Some(2) => img.fliph(),
+        Some(3) => img.rotate180(),
+        Some(4) => img.flipv(),
+        Some(5) => img.rotate90().fliph(),
+        Some(6) => img.rotate90(),
+        Some(7) => img.rotate270().fliph(),
+        Some(8) => img.rotate270(),
+        _ => img,
+    }
+}
+
+// JPEGs are a list of segments, each starting with a `0xFF` byte and a marker,
+// followed by their length, until the image data itself begins
+fn jpeg(data: &[u8]) -> Option<(Vec<u8>, Exif)> {
+    let mut out = data[0..2].to_vec();
+    let mut location = false;
+    let mut orientation = None;
+    let mut i = 2;
+
+    while i + 4 <= data.len() {
+        if data[i] != 0xFF {
+            return None;
+        }
+
+        let marker = data[i + 1];
+
+        // Once the Start of Scan segment is reached, everything left is image
+        // data, so it's copied as it is
+        if marker == 0xDA {
+            out.extend_from_slice(&data[i..]);
+            return Some((
+                out,
+                Exif {
+                    location,
+                    orientation,
+                },
+            ));
+        }
+
+        let len = u16::from_be_bytes([data[i + 2], data[i + 3]]) as usize;
+        let end = i + 2 + len;
+
+        if len < 2 || end > data.len() {
+            return None;
+        }
+
+        let body = &data[i + 4..end];
+
+        match marker {
+            // APP1 holds both EXIF and XMP data
+            0xE1 => {
+                if let Some(tiff) = body.strip_prefix(b"Exif\0\0") {
+                    let exif = read_exif(tiff);
+                    location |= exif.location;
+                    orientation = orientation.or(exif.orientation);
+
+                    if let Some(tiff) = exif.orientation.map(orientation_exif) {
+                        out.extend_from_slice(&[0xFF, 0xE1]);
+                        out.extend_from_slice(&(tiff.len() as u16 + 8).to_be_bytes());
+                        out.extend_from_slice(b"Exif\0\0");
+                        out.extend_from_slice(&tiff);
+                    }
+                } else {
+                    location |= xmp_location(body);
+                }
+            }
+            // APP2 holds ICC profiles, APP13 holds IPTC data and `0xFE` is a
+            // comment, and all of them are removed too
+            0xE2 | 0xED | 0xFE => {}
+            _ => out.extend_from_slice(&data[i..end]),
+        }
+
+        i = end;
+    }
+
+    None
+}
+
+// PNGs are made of chunks, and the ones that store metadata are all ancillary,
+// so removing them doesn't affect the image
+fn png(data: &[u8]) -> Option<(Vec<u8>, Exif)> {
+    let mut out = data[0..8].to_vec();
+    let mut location = false;
+    let mut orientation = None;
+    let mut i = 8;
+
+    while i + 12 <= data.len() {
+        let len = u32::from_be_bytes([data[i], data[i + 1], data[i + 2], data[i + 3]]) as usize;
+        let kind = &data[i + 4..i + 8];
+        let end = i + 12 + len;
+
+        if end > data.len() {
+            return None;
+        }
+
+        let body = &data[i + 8..i + 8 + len];
+
+        match kind {
+            b"eXIf" => {
+                let exif = read_exif(body);
+                location |= exif.location;
+                orientation = orientation.or(exif.orientation);
+
+                if let Some(tiff) = exif.orientation.map(orientation_exif) {
+                    let mut crc = Crc::new();
+                    crc.update(b"eXIf");
+                    crc.update(&tiff);
+
+                    out.extend_from_slice(&(tiff.len() as u32).to_be_bytes());
+                    out.extend_from_slice(b"eXIf");
+                    out.extend_from_slice(&tiff);
+                    out.extend_from_slice(&crc.sum().to_be_bytes());
+                }
+            }
+            // XMP is stored in an `iTXt` chunk, while the others are plain
+            // text comments, which can also hold personal information
+            b"iTXt" | b"tEXt" | b"zTXt" => location |= xmp_location(body),
+            b"iCCP" | b"tIME" => {}
+            _ => out.extend_from_slice(&data[i..end]),
+        }
+
+        i = end;
+    }
+
+    Some((
+        out,
+        Exif {
+            location,
+            orientation,
+        },
+    ))
+}
+
+// WebPs use the RIFF container, where metadata is stored in `EXIF`, `XMP ` and
+// `ICCP` chunks, with flags in the `VP8X` chunk saying which ones exist
+fn webp(data: &[u8]) -> Option<(Vec<u8>, Exif)> {
+    let mut out = data[0..12].to_vec();
+    let mut location = false;
+    let mut orientation = None;
+    let mut i = 12;
+
+    // Where the `VP8X` flags are in `out`, so that the EXIF flag can be set
+    // again if the orientation is kept
+    let mut flags = None;
+    let mut kept = false;
+
+    while i + 8 <= data.len() {
+        let kind = &data[i..i + 4];
+        let len = u32::from_le_bytes([data[i + 4], data[i + 5], data[i + 6], data[i + 7]]) as usize;
+
+        // Chunks are padded to an even length
+        let end = (i + 8 + len + (len & 1)).min(data.len());
+
+        if i + 8 + len > data.len() {
+            return None;
+        }
+
+        let body = &data[i + 8..i + 8 + len];
+
+        match kind {
+            b"EXIF" => {
+                let exif = read_exif(body.strip_prefix(b"Exif\0\0").unwrap_or(body));
+                location |= exif.location;
+                orientation = orientation.or(exif.orientation);
+
+                if let Some(tiff) = exif.orientation.map(orientation_exif) {
+                    out.extend_from_slice(b"EXIF");
+                    out.extend_from_slice(&(tiff.len() as u32).to_le_bytes());
+                    out.extend_from_slice(&tiff);
+                    kept = true;
+                }
+            }
+            b"XMP " => location |= xmp_location(body),
+            b"ICCP" => {}
+            b"VP8X" if len >= 1 => {
+                let start = out.len();
+                out.extend_from_slice(&data[i..end]);
+
+                // The ICC, EXIF and XMP flags are cleared, since those chunks
+                // won't be there anymore
+                out[start + 8] &= !(0x20 | 0x08 | 0x04);
+                flags = Some(start + 8);
+            }
+            _ => out.extend_from_slice(&data[i..end]),
+        }
+
+        i = end;
+    }
+
+    if let (Some(flags), true) = (flags, kept) {
+        out[flags] |= 0x08;
+    }
+
+    // The RIFF header stores the size of the rest of the file, which changed
+    let size = (out.len() - 8) as u32;
+    out[4..8].copy_from_slice(&size.to_le_bytes());
+
+    Some((
+        out,
+        Exif {
+            location,
+            orientation,
+        },
+    ))
+}
+
+struct Exif {
+    location: bool,
+    // Only set if the image isn't already the right way up
+    orientation: Option<u16>,
+}
+
+// EXIF data is a TIFF file, where the first directory of tags can point to a
+// GPS one, and the location only counts if that has a latitude or longitude
+fn read_exif(tiff: &[u8]) -> Exif {
+    let mut exif = Exif {
+        location: false,
+        orientation: None,
+    };
+
+    let little = match tiff.get(0..2) {
+        Some(b"II") => true,
+        Some(b"MM") => false,
+        _ => return exif,
+    };
+
+    let u16_at = |i: usize| {
+        tiff.get(i..i + 2).map(|b| {
+            if little {
+                u16::from_le_bytes([b[0], b[1]])
+            } else {
+                u16::from_be_bytes([b[0], b[1]])
+            }
+        })
+    };
+
+    let u32_at = |i: usize| {
+        tiff.get(i..i + 4).map(|b| {
+            if little {
+                u32::from_le_bytes([b[0], b[1], b[2], b[3]])
+            } else {
+                u32::from_be_bytes([b[0], b[1], b[2], b[3]])
+            }
+        })
+    };
+
+    // Each directory starts with the number of entries, which are 12 bytes
+    // long and start with their tag
+    let find = |dir: usize, tags: &[u16]| -> Option<usize> {
+        let count = u16_at(dir)? as usize;
+
+        (0..count)
+            .map(|n| dir + 2 + n * 12)
+            .find(|&entry| u16_at(entry).is_some_and(|tag| tags.contains(&tag)))
+    };
+
+    let ifd0 = match u32_at(4) {
+        Some(offset) => offset as usize,
+        None => return exif,
+    };
+
+    // Tag `0x8825` points to the GPS directory, where `0x0002` is the latitude
+    // and `0x0004` is the longitude
+    exif.location = find(ifd0, &[0x8825])
+        .and_then(|entry| u32_at(entry + 8))
+        .and_then(|gps| find(gps as usize, &[0x0002, 0x0004]))
+        .is_some();
+
+    // Tag `0x0112` is the orientation, a short stored right in the entry,
+    // where `1` means the image is already the right way up and anything
+    // above `8` isn't valid
+    exif.orientation = find(ifd0, &[0x0112])
+        .and_then(|entry| u16_at(entry + 8))
+        .filter(|orientation| (2..=8).contains(orientation));
+
+    exif
+}
+
+// This is the smallest valid EXIF block: a big-endian TIFF header followed by
+// a single directory with only the orientation in it
+fn orientation_exif(orientation: u16) -> Vec<u8> {
+    let mut tiff = b"MM\0\x2A".to_vec();
+    // The directory starts right after the header
+    tiff.extend_from_slice(&8u32.to_be_bytes());
+    tiff.extend_from_slice(&1u16.to_be_bytes());
+    // The tag, its type (a short), how many values it has and the value,
+    // padded to four bytes
+    tiff.extend_from_slice(&0x0112u16.to_be_bytes());
+    tiff.extend_from_slice(&3u16.to_be_bytes());
+    tiff.extend_from_slice(&1u32.to_be_bytes());
+    tiff.extend_from_slice(&orientation.to_be_bytes());
+    tiff.extend_from_slice(&[0, 0]);
+    // There's no next directory
+    tiff.extend_from_slice(&0u32.to_be_bytes());
+    tiff
+}
+
+// XMP is plain XML, so it's enough to search for the GPS properties' names
+fn xmp_location(xmp: &[u8]) -> bool {
+    let text = String::from_utf8_lossy(xmp);
+
+    text.contains("GPSLatitude") || text.contains("GPSLongitude")
+}
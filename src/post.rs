@@ -0,0 +1,115 @@
+use {
+    crate::{endpoint::Endpoints, error},
+    reqwest::{
+        multipart::{Form, Part},
+        Client,
+    },
+    serde_json::json,
+    std::{
+        fs::{metadata, read},
+        path::{Path, PathBuf},
+    },
+};
+
+// Results can either be posted by the bot itself, in any channel it can send
+// messages in, or through a webhook, which doesn't need the bot to be in the
+// server at all
+#[derive(Clone, Debug)]
+pub enum Target {
+    Channel(String),
+    Webhook(String),
+}
+
+#[derive(Clone, Debug)]
+pub struct Options {
+    pub target: Target,
+    // Whether the collage (or any other generated file) should be uploaded
+    // along with the summary
+    pub attach: bool,
+    // Discord rejects uploads above a certain size, which depends on the
+    // server's boost level, so it's configurable
+    pub limit: u64,
+}
+
+// The upload limit for servers without boosts, in bytes
+pub const DEFAULT_LIMIT: u64 = 10 * 1024 * 1024;
+
+// Discord only accepts 10 files per message
+const MAX_FILES: usize = 10;
+
+// This posts the run's summary, followed by the selected files, splitting them
+// into several messages if they don't fit in one. Files larger than the limit
+// are skipped, and mentioned in the summary so people know they're missing.
+pub async fn send(
+    client: &Client,
+    endpoints: &Endpoints,
+    token: &str,
+    options: &Options,
+    summary: &str,
+    files: &[PathBuf],
+) -> error::Result<()> {
+    let mut content = summary.to_string();
+    let mut batches: Vec<Vec<(PathBuf, u64)>> = Vec::new();
+    let mut current: Vec<(PathBuf, u64)> = Vec::new();
+    let mut size = 0;
+
+    if options.attach {
+        for file in files {
+            let len = metadata(file)?.len();
+
+            if len > options.limit {
+                content.push_str(&format!(
+                    "\n`{}` is too large to upload ({:.1} MB).",
+                    name(file),
+                    len as f64 / 1024.0 / 1024.0
+                ));
+                continue;
+            }
+
+            // The limit applies to the whole message, so a new one is started
+            // whenever the next file wouldn't fit in the current one
+            if size + len > options.limit || current.len() == MAX_FILES {
+                batches.push(current);
+                current = Vec::new();
+                size = 0;
+            }
+
+            size += len;
+            current.push((file.clone(), len));
+        }
+    }
+
+    batches.push(current);
+
+    for (i, batch) in batches.into_iter().enumerate() {
+        // Only the first message includes the summary, the rest are just the
+        // files that didn't fit in it
+        let text = if i == 0 { content.as_str() } else { "" };
+        let mut form = Form::new().text("payload_json", json!({ "content": text }).to_string());
+
+        for (n, (file, _)) in batch.iter().enumerate() {
+            let bytes = read(file)?;
+            form = form.part(
+                format!("files[{}]", n),
+                Part::bytes(bytes).file_name(name(file)),
+            );
+        }
+
+        let req = match &options.target {
+            Target::Channel(channel) => client
+                .post(endpoints.api(&format!("channels/{}/messages", channel)))
+                .header("Authorization", format!("Bot {}", token)),
+            Target::Webhook(url) => client.post(url),
+        };
+
+        error::check(req.multipart(form).send().await?).await?;
+    }
+
+    Ok(())
+}
+
+fn name(file: &Path) -> String {
+    file.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| String::from("file"))
+}
@@ -0,0 +1,136 @@
+use {
+    indicatif::{ProgressBar, ProgressStyle},
+    std::{
+        path::PathBuf,
+        sync::atomic::{AtomicUsize, Ordering},
+    },
+};
+
+// Instead of printing a line for every file, the download reports what it's
+// doing through these events, which the CLI turns into a progress bar and
+// tools using the `Downloader` can handle however they want
+#[derive(Clone, Debug)]
+pub enum Event {
+    // A page of up to 100 messages was received from Discord
+    PageFetched {
+        messages: usize,
+    },
+    // An attachment passed the filters and will be downloaded, with `size`
+    // being the number of bytes Discord says it has
+    Queued {
+        url: String,
+        size: u64,
+    },
+    Saved {
+        url: String,
+        file: PathBuf,
+        bytes: u64,
+    },
+    Skipped {
+        url: String,
+        reason: Reason,
+    },
+    Failed {
+        url: String,
+        size: u64,
+        error: String,
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Reason {
+    // The attachment isn't an image
+    NotImage,
+    // The image didn't pass the size filters
+    Filtered,
+    // The message's text didn't pass the text filters
+    Message,
+    // Enough images were already taken from the same message or author
+    MessageLimit,
+    AuthorLimit,
+}
+
+// The bar measures bytes rather than images, as they vary a lot in size and
+// the speed would be meaningless otherwise. Its length grows as pages are
+// fetched, since the total can't be known without walking the whole channel.
+pub struct Bar {
+    bar: ProgressBar,
+    pages: AtomicUsize,
+    saved: AtomicUsize,
+    skipped: AtomicUsize,
+    failed: AtomicUsize,
+}
+
+impl Bar {
+    pub fn new() -> Bar {
+        let bar = ProgressBar::new(0);
+
+        bar.set_style(
+            ProgressStyle::with_template(
+                "[{elapsed_precise}] [{bar:30}] {bytes}/{total_bytes} ({binary_bytes_per_sec}, ETA {eta}) {msg}",
+            )
+            .unwrap_or_else(|_| ProgressStyle::default_bar())
+            .progress_chars("=> "),
+        );
+
+        Bar {
+            bar,
+            pages: AtomicUsize::new(0),
+            saved: AtomicUsize::new(0),
+            skipped: AtomicUsize::new(0),
+            failed: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn handle(&self, event: Event) {
+        match event {
+            Event::PageFetched { .. } => {
+                self.pages.fetch_add(1, Ordering::Relaxed);
+            }
+            Event::Queued { size, .. } => self.bar.inc_length(size),
+            Event::Saved { file, bytes, .. } => {
+                self.saved.fetch_add(1, Ordering::Relaxed);
+                self.bar.inc(bytes);
+
+                // Lines are printed above the bar, and still show up if the
+                // output isn't a terminal and the bar is hidden
+                if let Some(name) = file.file_name() {
+                    self.bar
+                        .suspend(|| println!("Saved {}!", name.to_string_lossy()));
+                }
+            }
+            Event::Skipped { .. } => {
+                self.skipped.fetch_add(1, Ordering::Relaxed);
+            }
+            // Failures are listed once the download is over, along with the
+            // report's other errors
+            Event::Failed { size, .. } => {
+                self.failed.fetch_add(1, Ordering::Relaxed);
+                self.bar.inc(size);
+            }
+        }
+
+        self.bar.set_message(format!(
+            "{} saved, {} skipped, {} failed, {} page{}",
+            self.saved.load(Ordering::Relaxed),
+            self.skipped.load(Ordering::Relaxed),
+            self.failed.load(Ordering::Relaxed),
+            self.pages.load(Ordering::Relaxed),
+            if self.pages.load(Ordering::Relaxed) == 1 {
+                ""
+            } else {
+                "s"
+            }
+        ));
+    }
+
+    pub fn finish(&self) {
+        self.bar.finish_and_clear();
+    }
+}
+
+impl Default for Bar {
+    fn default() -> Self {
+        Bar::new()
+    }
+}
@@ -0,0 +1,69 @@
+use {
+    crate::format::Format,
+    image::{imageops::FilterType, GenericImageView, ImageResult},
+    std::{
+        fs::create_dir_all,
+        path::{Path, PathBuf},
+    },
+};
+
+// Thumbnails are stored in a `thumbs` folder next to the images they were made
+// from, and keep the original's name so that they're easy to match up
+pub const FOLDER: &str = "thumbs";
+
+#[derive(Clone, Debug)]
+pub struct Options {
+    // The longest side of the thumbnail, in pixels, so that portrait and
+    // landscape images end up being roughly the same size
+    pub max_edge: u32,
+    pub format: Format,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            max_edge: 320,
+            format: Format::Jpeg(80),
+        }
+    }
+}
+
+// This returns the path the thumbnail of an image would be saved to, whether
+// it exists or not
+pub fn path(image: &Path, options: &Options) -> Option<PathBuf> {
+    let dir = image.parent()?.join(FOLDER);
+    let name = image.file_stem()?.to_str()?;
+
+    Some(dir.join(format!("{}.{}", name, options.format.extension())))
+}
+
+// The thumbnail is only generated if it doesn't exist yet, so that running the
+// program again on the same folder doesn't redo all the work. The returned
+// `bool` is `true` if a new thumbnail was made.
+pub fn create(image: &Path, options: &Options) -> ImageResult<(PathBuf, bool)> {
+    let thumb = path(image, options).ok_or_else(|| {
+        std::io::Error::new(std::io::ErrorKind::InvalidInput, "Invalid image path!")
+    })?;
+
+    if thumb.exists() {
+        return Ok((thumb, false));
+    }
+
+    if let Some(dir) = thumb.parent() {
+        create_dir_all(dir)?;
+    }
+
+    let img = image::open(image)?;
+
+    // Images that are already smaller than the thumbnail are left as they
+    // are, since scaling them up would only make them blurry
+    let img = if img.width().max(img.height()) > options.max_edge {
+        img.resize(options.max_edge, options.max_edge, FilterType::Triangle)
+    } else {
+        img
+    };
+
+    options.format.write(&img, &thumb)?;
+
+    Ok((thumb, true))
+}
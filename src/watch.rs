@@ -0,0 +1,84 @@
+use {
+    crate::{config::Config, gateway, run},
+    serenity::{
+        async_trait,
+        client::{bridge::gateway::GatewayIntents, Context, EventHandler},
+        model::{channel::Message, gateway::Ready},
+    },
+};
+
+// Discord only sends the content and attachments of messages to bots that ask
+// for the Message Content intent, which `serenity` doesn't know about yet
+const MESSAGE_CONTENT: u64 = 1 << 15;
+
+// While `run::all()` requests the channel's history through the API, this
+// mode connects to Discord's gateway instead, which sends every new message
+// as soon as it's posted, so the program can run in the background and save
+// the images as they come in
+pub async fn all(selected: Config) {
+    println!();
+
+    let intents = GatewayIntents {
+        bits: (GatewayIntents::GUILD_MESSAGES | GatewayIntents::DIRECT_MESSAGES).bits()
+            | MESSAGE_CONTENT,
+    };
+
+    // The connection only ends if it can't be recovered, which mostly happens
+    // if the Message Content intent isn't enabled for the bot
+    if let Err(err) = gateway::connect(&selected.clone(), Handler { selected }, intents).await {
+        println!("\nLost connection to Discord: {}", err);
+        println!("Make sure the `Message Content Intent` is enabled in the bot's settings.");
+    }
+}
+
+struct Handler {
+    selected: Config,
+}
+
+#[async_trait]
+impl EventHandler for Handler {
+    async fn ready(&self, _: Context, ready: Ready) {
+        println!("Connected as {}!", ready.user.name);
+        println!("Watching for new images, close the program to stop.\n");
+    }
+
+    async fn message(&self, _: Context, msg: Message) {
+        // The gateway sends messages from every channel the bot can see, so
+        // the ones from other channels are ignored
+        let channel = msg.channel_id.to_string();
+
+        if (channel != self.selected.channel && !self.selected.channels.contains(&channel))
+            || !self.selected.filter.message(&msg)
+        {
+            return;
+        }
+
+        // The attachments are checked exactly like in `run::all()`, and go
+        // through the same steps once they're saved
+        for att in msg.attachments.iter() {
+            if att.width.is_some() && self.selected.filter.attachment(att) {
+                // A failed download only skips that image, as the next ones
+                // might still work
+                let image = match run::process(&att.url, &self.selected).await {
+                    Ok(image) => image,
+                    Err(err) => {
+                        println!("Failed to save {}: {}", att.url, err);
+                        continue;
+                    }
+                };
+
+                if let Some(name) = image.file.file_name() {
+                    println!("Saved {}!", name.to_string_lossy());
+                }
+
+                for err in &image.errors {
+                    println!("{}", err);
+                }
+
+                if image.location {
+                    println!("Removed location data from {}!", image.file.display());
+                }
+            }
+        }
+    }
+}
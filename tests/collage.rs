@@ -0,0 +1,50 @@
+use {
+    discord_image_downloader::collage::{self, Layout, Options},
+    image::{Rgb, RgbImage},
+    std::path::PathBuf,
+    tempfile::TempDir,
+};
+
+// Phone screenshots are a bit more than twice as tall as they're wide
+fn screenshots(dir: &TempDir, count: usize) -> Vec<PathBuf> {
+    (0..count)
+        .map(|n| {
+            let path = dir.path().join(format!("{}.png", n));
+            RgbImage::from_pixel(9, 20, Rgb([30, 30, 200]))
+                .save(&path)
+                .unwrap();
+            path
+        })
+        .collect()
+}
+
+#[test]
+fn masonry_adds_columns_for_lots_of_images() {
+    let dir = TempDir::new().unwrap();
+    let images = screenshots(&dir, 1000);
+
+    let options = Options {
+        layout: Layout::Masonry,
+        width: 1024,
+        ..Options::default()
+    };
+
+    // With four columns, this would be over 100,000 pixels tall
+    let canvas = collage::render(&images, &options).unwrap();
+    assert_eq!(canvas.width(), 1024);
+    assert!(canvas.height() < 2048, "{}", canvas.height());
+}
+
+#[test]
+fn refuses_canvases_that_are_too_big() {
+    let dir = TempDir::new().unwrap();
+    let images = screenshots(&dir, 2);
+
+    let options = Options {
+        width: 100_000,
+        height: 100_000,
+        ..Options::default()
+    };
+
+    assert!(collage::render(&images, &options).is_err());
+}
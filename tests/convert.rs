@@ -0,0 +1,36 @@
+use {
+    discord_image_downloader::convert::{normalize, Options},
+    image::{DynamicImage, GenericImageView, ImageOutputFormat, RgbImage},
+    std::fs::write,
+    tempfile::TempDir,
+};
+
+// A big-endian TIFF with a single entry, saying the photo has to be rotated
+// 90 degrees clockwise to be shown the right way up
+const ROTATED: &[u8] = b"MM\0\x2A\0\0\0\x08\0\x01\x01\x12\0\x03\0\0\0\x01\0\x06\0\0\0\0\0\0";
+
+#[test]
+fn turns_sideways_photos_upright() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("photo.jpg");
+
+    let mut jpeg = Vec::new();
+    DynamicImage::ImageRgb8(RgbImage::new(2, 3))
+        .write_to(&mut jpeg, ImageOutputFormat::Jpeg(90))
+        .unwrap();
+
+    // The EXIF segment goes right after the start of the image
+    let body = [b"Exif\0\0".as_ref(), ROTATED].concat();
+    let mut segment = vec![0xFF, 0xE1];
+    segment.extend_from_slice(&(body.len() as u16 + 2).to_be_bytes());
+    segment.extend_from_slice(&body);
+    jpeg.splice(2..2, segment);
+    write(&path, jpeg).unwrap();
+
+    let converted = normalize(&path, &Options::default()).unwrap();
+
+    // PNGs have no orientation tag, so the pixels themselves are rotated
+    assert_eq!(converted, dir.path().join("photo.png"));
+    let img = image::open(&converted).unwrap();
+    assert_eq!((img.width(), img.height()), (3, 2));
+}
@@ -0,0 +1,836 @@
+mod mock;
+
+use {
+    chrono::{TimeZone, Utc},
+    discord_image_downloader::{
+        archive, collage, config::Order, convert, downloader::Downloader, error::Error, gallery,
+        run, thumbnail,
+    },
+    mock::{custom_emoji, emoji, file, id, image, message, photo, react, Mock, GUILD, TOKEN},
+    serde_json::Value,
+    std::fs::{read, read_dir, read_to_string},
+    tempfile::TempDir,
+};
+
+const CHANNEL: u64 = 850000000000000001;
+
+fn downloader(mock: &Mock, dir: &TempDir) -> Downloader {
+    Downloader::new(TOKEN, &CHANNEL.to_string())
+        .endpoints(mock.endpoints())
+        .path(dir.path())
+}
+
+// One message with one image for each `n`, all posted on the given day
+fn history(year: i32, month: u32, day: u32, count: u64) -> Vec<Value> {
+    (0..count)
+        .map(|n| {
+            message(
+                id(year, month, day, n * 10),
+                CHANNEL,
+                "designer",
+                "",
+                vec![image(
+                    id(year, month, day, n * 10 + 1),
+                    CHANNEL,
+                    &format!("{}.png", n),
+                )],
+            )
+        })
+        .collect()
+}
+
+fn saved_files(dir: &TempDir) -> usize {
+    read_dir(dir.path())
+        .unwrap()
+        .filter(|entry| entry.as_ref().unwrap().path().is_file())
+        .count()
+}
+
+#[tokio::test]
+async fn saves_every_image_attachment() {
+    let mock = Mock::start().await;
+    let dir = TempDir::new().unwrap();
+
+    let first = id(2021, 1, 1, 0);
+    let second = id(2021, 1, 1, 10);
+    let third = id(2021, 1, 1, 20);
+
+    mock.add(
+        CHANNEL,
+        vec![
+            message(
+                first,
+                CHANNEL,
+                "designer",
+                "two at once",
+                vec![
+                    image(first + 1, CHANNEL, "a.png"),
+                    image(first + 2, CHANNEL, "b.png"),
+                ],
+            ),
+            message(
+                second,
+                CHANNEL,
+                "designer",
+                "not an image",
+                vec![file(second + 1, CHANNEL, "notes.txt")],
+            ),
+            message(
+                third,
+                CHANNEL,
+                "designer",
+                "",
+                vec![image(third + 1, CHANNEL, "c.png")],
+            ),
+        ],
+    );
+
+    let config = downloader(&mock, &dir).config().clone();
+    let report = run::all(config).await.unwrap();
+
+    assert_eq!(report.saved.len(), 3);
+    assert!(report.errors.is_empty());
+    assert_eq!(saved_files(&dir), 3);
+
+    for att in [first + 1, first + 2, third + 1].iter() {
+        assert!(dir.path().join(format!("{}.png", att)).exists());
+    }
+}
+
+#[tokio::test]
+async fn follows_pagination_through_the_whole_range() {
+    let mock = Mock::start().await;
+    let dir = TempDir::new().unwrap();
+
+    mock.add(CHANNEL, history(2021, 1, 1, 250));
+
+    let report = downloader(&mock, &dir)
+        .since(Utc.ymd(2020, 12, 31).and_hms(0, 0, 0))
+        .run()
+        .await
+        .unwrap();
+
+    assert_eq!(report.saved.len(), 250);
+    assert_eq!(saved_files(&dir), 250);
+
+    // Three full pages, and an empty one that ends the download
+    let pages = mock.pages();
+    assert_eq!(pages.len(), 4);
+    assert!(pages.iter().all(|page| page.contains("after=")));
+}
+
+#[tokio::test]
+async fn stops_at_the_quantity_limit() {
+    let mock = Mock::start().await;
+    let dir = TempDir::new().unwrap();
+
+    mock.add(CHANNEL, history(2021, 1, 1, 20));
+
+    let report = downloader(&mock, &dir)
+        .since(Utc.ymd(2020, 12, 31).and_hms(0, 0, 0))
+        .limit(5)
+        .run()
+        .await
+        .unwrap();
+
+    assert_eq!(report.saved.len(), 5);
+    assert_eq!(saved_files(&dir), 5);
+
+    // Going forward from the Start Date, the oldest images come first, and
+    // the rest of the page isn't needed
+    for n in 0..5 {
+        let att = id(2021, 1, 1, n * 10 + 1);
+        assert!(dir.path().join(format!("{}.png", att)).exists());
+    }
+
+    assert_eq!(mock.pages().len(), 1);
+}
+
+#[tokio::test]
+async fn walks_the_whole_history_without_a_start_date() {
+    let mock = Mock::start().await;
+    let dir = TempDir::new().unwrap();
+
+    mock.add(CHANNEL, history(2021, 1, 1, 250));
+
+    let report = downloader(&mock, &dir).run().await.unwrap();
+
+    assert_eq!(report.saved.len(), 250);
+    assert!(mock.pages()[0].ends_with("after=0"));
+}
+
+#[tokio::test]
+async fn newest_first_keeps_the_latest_images() {
+    let mock = Mock::start().await;
+    let dir = TempDir::new().unwrap();
+
+    mock.add(CHANNEL, history(2021, 1, 1, 250));
+
+    let report = downloader(&mock, &dir)
+        .order(Order::Newest)
+        .limit(5)
+        .run()
+        .await
+        .unwrap();
+
+    assert_eq!(report.saved.len(), 5);
+
+    for n in 245..250 {
+        let att = id(2021, 1, 1, n * 10 + 1);
+        assert!(dir.path().join(format!("{}.png", att)).exists());
+    }
+
+    // Only the newest page was needed, without walking the whole history
+    let pages = mock.pages();
+    assert_eq!(pages.len(), 1);
+    assert!(!pages[0].contains("after=") && !pages[0].contains("before="));
+}
+
+#[tokio::test]
+async fn newest_first_pages_backwards_to_the_start_date() {
+    let mock = Mock::start().await;
+    let dir = TempDir::new().unwrap();
+
+    mock.add(CHANNEL, history(2021, 1, 1, 50));
+    mock.add(CHANNEL, history(2021, 3, 1, 150));
+
+    let report = downloader(&mock, &dir)
+        .order(Order::Newest)
+        .since(Utc.ymd(2021, 2, 1).and_hms(0, 0, 0))
+        .run()
+        .await
+        .unwrap();
+
+    assert_eq!(report.saved.len(), 150);
+
+    // The newest images are saved first
+    assert_eq!(
+        report.saved[0],
+        dir.path().join(format!("{}.png", id(2021, 3, 1, 1491)))
+    );
+
+    // The second page reaches January, so there's no need for a third
+    let pages = mock.pages();
+    assert_eq!(pages.len(), 2);
+    assert!(pages[1].contains(&format!("before={}", id(2021, 3, 1, 500))));
+}
+
+#[tokio::test]
+async fn skips_messages_before_the_start_date() {
+    let mock = Mock::start().await;
+    let dir = TempDir::new().unwrap();
+
+    mock.add(CHANNEL, history(2021, 1, 1, 3));
+    mock.add(CHANNEL, history(2021, 3, 1, 2));
+
+    let report = downloader(&mock, &dir)
+        .since(Utc.ymd(2021, 2, 1).and_hms(0, 0, 0))
+        .run()
+        .await
+        .unwrap();
+
+    assert_eq!(report.saved.len(), 2);
+
+    for n in 0..2 {
+        let att = id(2021, 3, 1, n * 10 + 1);
+        assert!(dir.path().join(format!("{}.png", att)).exists());
+    }
+}
+
+#[tokio::test]
+async fn reports_discord_errors() {
+    let mock = Mock::start().await;
+    let dir = TempDir::new().unwrap();
+
+    mock.forbid(CHANNEL);
+
+    match downloader(&mock, &dir).run().await {
+        Err(Error::Permission(message)) => assert_eq!(message, "Missing Access"),
+        other => panic!("Expected a permission error, got {:?}", other.err()),
+    }
+
+    match Downloader::new(TOKEN, "123")
+        .endpoints(mock.endpoints())
+        .path(dir.path())
+        .run()
+        .await
+    {
+        Err(Error::Discord { status, code, .. }) => {
+            assert_eq!(status, 404);
+            assert_eq!(code, 10003);
+        }
+        other => panic!("Expected an unknown channel, got {:?}", other.err()),
+    }
+
+    match Downloader::new("wrong", &CHANNEL.to_string())
+        .endpoints(mock.endpoints())
+        .path(dir.path())
+        .run()
+        .await
+    {
+        Err(Error::Discord { status, .. }) => assert_eq!(status, 401),
+        other => panic!("Expected an authorization error, got {:?}", other.err()),
+    }
+}
+
+#[tokio::test]
+async fn keeps_going_when_an_image_fails() {
+    let mock = Mock::start().await;
+    let dir = TempDir::new().unwrap();
+
+    mock.add(CHANNEL, history(2021, 1, 1, 3));
+    mock.break_attachment(id(2021, 1, 1, 11));
+
+    let report = downloader(&mock, &dir)
+        .since(Utc.ymd(2020, 12, 31).and_hms(0, 0, 0))
+        .run()
+        .await
+        .unwrap();
+
+    assert_eq!(report.saved.len(), 2);
+    assert_eq!(report.errors.len(), 1);
+}
+
+#[tokio::test]
+async fn dry_run_only_lists_images() {
+    let mock = Mock::start().await;
+    let dir = TempDir::new().unwrap();
+    let export = dir.path().join("images.csv");
+
+    mock.add(CHANNEL, history(2021, 1, 1, 4));
+
+    let report = downloader(&mock, &dir)
+        .since(Utc.ymd(2020, 12, 31).and_hms(0, 0, 0))
+        .dry_run(true)
+        .export(&export)
+        .run()
+        .await
+        .unwrap();
+
+    assert!(report.saved.is_empty());
+    assert_eq!(report.matched.len(), 4);
+    assert_eq!(report.bytes(), 4 * mock::png().len() as u64);
+
+    // Nothing is downloaded, and the only file is the exported list
+    assert!(!mock
+        .requests()
+        .iter()
+        .any(|req| req.contains("/attachments/")));
+    assert_eq!(saved_files(&dir), 1);
+    assert_eq!(read_to_string(&export).unwrap().lines().count(), 5);
+}
+
+// Several images posted in one message, all by the same author
+fn album(year: i32, month: u32, day: u32, author: &str, count: u64) -> Value {
+    let msg = id(year, month, day, 0);
+
+    message(
+        msg,
+        CHANNEL,
+        author,
+        "",
+        (1..=count)
+            .map(|n| image(msg + n, CHANNEL, &format!("{}.png", n)))
+            .collect(),
+    )
+}
+
+#[tokio::test]
+async fn limits_count_images_rather_than_messages() {
+    let mock = Mock::start().await;
+    let dir = TempDir::new().unwrap();
+
+    mock.add(CHANNEL, vec![album(2021, 1, 1, "designer", 5)]);
+    mock.add(CHANNEL, history(2021, 1, 2, 3));
+
+    let report = downloader(&mock, &dir)
+        .since(Utc.ymd(2020, 12, 31).and_hms(0, 0, 0))
+        .limit(3)
+        .run()
+        .await
+        .unwrap();
+
+    assert_eq!(report.saved.len(), 3);
+    assert_eq!(saved_files(&dir), 3);
+}
+
+#[tokio::test]
+async fn caps_images_per_message() {
+    let mock = Mock::start().await;
+    let dir = TempDir::new().unwrap();
+
+    mock.add(CHANNEL, vec![album(2021, 1, 1, "designer", 5)]);
+    mock.add(CHANNEL, vec![album(2021, 1, 2, "designer", 1)]);
+
+    let report = downloader(&mock, &dir)
+        .since(Utc.ymd(2020, 12, 31).and_hms(0, 0, 0))
+        .per_message(2)
+        .run()
+        .await
+        .unwrap();
+
+    assert_eq!(report.saved.len(), 3);
+
+    // The first images of each message are the ones kept
+    let first = id(2021, 1, 1, 0);
+    for att in [first + 1, first + 2].iter() {
+        assert!(dir.path().join(format!("{}.png", att)).exists());
+    }
+}
+
+#[tokio::test]
+async fn caps_images_per_author() {
+    let mock = Mock::start().await;
+    let dir = TempDir::new().unwrap();
+
+    mock.add(CHANNEL, history(2021, 1, 1, 10));
+    mock.add(CHANNEL, vec![album(2021, 1, 2, "photographer", 2)]);
+
+    let report = downloader(&mock, &dir)
+        .since(Utc.ymd(2020, 12, 31).and_hms(0, 0, 0))
+        .per_author(3)
+        .limit(6)
+        .run()
+        .await
+        .unwrap();
+
+    // Three from the designer, who posted ten, and both of the photographer's
+    assert_eq!(report.saved.len(), 5);
+    assert_eq!(
+        report
+            .matched
+            .iter()
+            .filter(|m| m.author == "photographer")
+            .count(),
+        2
+    );
+}
+
+#[tokio::test]
+async fn random_samples_are_reproducible() {
+    let mock = Mock::start().await;
+
+    mock.add(CHANNEL, history(2021, 1, 1, 250));
+
+    let sample = |seed| {
+        let dir = TempDir::new().unwrap();
+        let downloader = downloader(&mock, &dir)
+            .order(Order::Random(seed))
+            .limit(10)
+            .dry_run(true);
+
+        async move {
+            let report = downloader.run().await.unwrap();
+            report.matched.iter().map(|m| m.id).collect::<Vec<u64>>()
+        }
+    };
+
+    let first = sample(7).await;
+
+    // The whole range is looked at before picking anything
+    assert_eq!(first.len(), 10);
+    assert_eq!(mock.pages().len(), 4);
+
+    // The sample keeps the order the images were posted in
+    assert!(first.windows(2).all(|pair| pair[0] < pair[1]));
+
+    assert_eq!(sample(7).await, first);
+    assert_ne!(sample(8).await, first);
+}
+
+#[tokio::test]
+async fn downloads_the_random_sample() {
+    let mock = Mock::start().await;
+    let dir = TempDir::new().unwrap();
+
+    mock.add(CHANNEL, history(2021, 1, 1, 30));
+
+    let report = downloader(&mock, &dir)
+        .order(Order::Random(42))
+        .limit(4)
+        .run()
+        .await
+        .unwrap();
+
+    assert_eq!(report.saved.len(), 4);
+    assert_eq!(saved_files(&dir), 4);
+
+    for m in &report.matched {
+        assert!(dir.path().join(format!("{}.png", m.id)).exists());
+    }
+}
+
+#[tokio::test]
+async fn caps_the_random_sample() {
+    let mock = Mock::start().await;
+
+    mock.add(CHANNEL, history(2021, 1, 1, 30));
+    mock.add(CHANNEL, vec![album(2021, 1, 2, "photographer", 2)]);
+
+    for seed in 0..5 {
+        let report = downloader(&mock, &TempDir::new().unwrap())
+            .since(Utc.ymd(2020, 12, 31).and_hms(0, 0, 0))
+            .order(Order::Random(seed))
+            .per_author(3)
+            .limit(5)
+            .dry_run(true)
+            .run()
+            .await
+            .unwrap();
+
+        // The caps are applied to the sample rather than to the first images
+        // posted, so it's still full and the designer's images are drawn
+        // from all thirty
+        let designer: Vec<u64> = report
+            .matched
+            .iter()
+            .filter(|m| m.author == "designer")
+            .map(|m| m.id)
+            .collect();
+        assert_eq!(report.matched.len(), 5);
+        assert_eq!(designer.len(), 3);
+        assert!(designer.iter().any(|image| *image > id(2021, 1, 1, 30)));
+    }
+}
+
+// Three images whose messages got 2, 9 and 5 fire reactions, while the first
+// one got plenty of a custom emoji too
+fn reacted() -> Vec<Value> {
+    history(2021, 1, 1, 3)
+        .into_iter()
+        .zip([2, 9, 5].iter())
+        .enumerate()
+        .map(|(n, (msg, fire))| {
+            let mut reactions = vec![emoji("🔥", *fire)];
+
+            if n == 0 {
+                reactions.push(custom_emoji(900, "pepe", 20));
+            }
+
+            react(msg, reactions)
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn keeps_the_most_reacted_images() {
+    let mock = Mock::start().await;
+    let dir = TempDir::new().unwrap();
+
+    mock.add(CHANNEL, reacted());
+    mock.add(CHANNEL, history(2021, 1, 2, 5));
+
+    let report = downloader(&mock, &dir)
+        .since(Utc.ymd(2020, 12, 31).and_hms(0, 0, 0))
+        .order(Order::Reactions(None))
+        .limit(2)
+        .run()
+        .await
+        .unwrap();
+
+    // Every reaction counts, so the first image has 22 and the second 9
+    let ranked: Vec<u64> = report.matched.iter().map(|m| m.id).collect();
+    assert_eq!(ranked, vec![id(2021, 1, 1, 1), id(2021, 1, 1, 11)]);
+    assert_eq!(saved_files(&dir), 2);
+}
+
+#[tokio::test]
+async fn ranks_by_a_single_emoji() {
+    let mock = Mock::start().await;
+
+    mock.add(CHANNEL, reacted());
+
+    let ranked = |emoji: &str| {
+        let dir = TempDir::new().unwrap();
+        let downloader = downloader(&mock, &dir)
+            .since(Utc.ymd(2020, 12, 31).and_hms(0, 0, 0))
+            .order(Order::Reactions(Some(emoji.to_string())))
+            .limit(1)
+            .dry_run(true);
+
+        async move {
+            let report = downloader.run().await.unwrap();
+            report.matched.iter().map(|m| m.id).collect::<Vec<u64>>()
+        }
+    };
+
+    assert_eq!(ranked("🔥").await, vec![id(2021, 1, 1, 11)]);
+
+    // Custom emojis can be written by name, ID or both
+    for custom in ["pepe", "900", "<:pepe:900>"].iter() {
+        assert_eq!(ranked(custom).await, vec![id(2021, 1, 1, 1)]);
+    }
+}
+
+#[tokio::test]
+async fn caps_the_ranked_images() {
+    let mock = Mock::start().await;
+
+    mock.add(CHANNEL, reacted());
+
+    let report = downloader(&mock, &TempDir::new().unwrap())
+        .since(Utc.ymd(2020, 12, 31).and_hms(0, 0, 0))
+        .order(Order::Reactions(Some(String::from("🔥"))))
+        .per_author(1)
+        .dry_run(true)
+        .run()
+        .await
+        .unwrap();
+
+    // The designer's most reacted image is kept, not the first they posted
+    let ranked: Vec<u64> = report.matched.iter().map(|m| m.id).collect();
+    assert_eq!(ranked, vec![id(2021, 1, 1, 11)]);
+}
+
+#[tokio::test]
+async fn makes_a_gallery_of_the_saved_images() {
+    let mock = Mock::start().await;
+    let dir = TempDir::new().unwrap();
+
+    let first = id(2021, 1, 1, 0);
+    let second = id(2021, 1, 2, 0);
+
+    mock.add(
+        CHANNEL,
+        vec![
+            message(
+                first,
+                CHANNEL,
+                "designer",
+                "",
+                vec![image(first + 1, CHANNEL, "a.png")],
+            ),
+            message(
+                second,
+                CHANNEL,
+                "<b>\"odd\" name</b>",
+                "",
+                vec![image(second + 1, CHANNEL, "b.png")],
+            ),
+        ],
+    );
+
+    let report = downloader(&mock, &dir)
+        .since(Utc.ymd(2020, 12, 31).and_hms(0, 0, 0))
+        .thumbnails(thumbnail::Options::default())
+        .gallery(true)
+        .run()
+        .await
+        .unwrap();
+
+    let file = dir.path().join(gallery::FILE);
+    assert_eq!(report.gallery.as_ref(), Some(&file));
+
+    let html = read_to_string(&file).unwrap();
+
+    // Images are linked relatively, showing their thumbnails
+    assert!(html.contains(&format!("href=\"{}.png\"", first + 1)));
+    assert!(html.contains(&format!("src=\"thumbs/{}.jpg\"", first + 1)));
+
+    // Along with the message they came from
+    assert!(html.contains(&format!(
+        "https://discord.com/channels/{}/{}/{}",
+        GUILD, CHANNEL, second
+    )));
+
+    // Both authors can be picked in the filter, with their names escaped
+    assert!(html.contains("<option value=\"designer\">designer</option>"));
+    assert!(html.contains("&lt;b&gt;&quot;odd&quot; name&lt;/b&gt;"));
+    assert!(!html.contains("<b>"));
+}
+
+#[tokio::test]
+async fn only_downloads_pinned_messages() {
+    let mock = Mock::start().await;
+    let dir = TempDir::new().unwrap();
+
+    // Every 50th message is pinned, including one from before the Start Date
+    let pin = |messages: Vec<Value>| {
+        messages
+            .into_iter()
+            .enumerate()
+            .map(|(n, mut msg)| {
+                msg["pinned"] = serde_json::json!(n % 50 == 0);
+                msg
+            })
+            .collect::<Vec<Value>>()
+    };
+
+    mock.add(CHANNEL, pin(history(2021, 1, 1, 10)));
+    mock.add(CHANNEL, pin(history(2021, 3, 1, 200)));
+
+    let report = downloader(&mock, &dir)
+        .since(Utc.ymd(2021, 2, 1).and_hms(0, 0, 0))
+        .pins(true)
+        .run()
+        .await
+        .unwrap();
+
+    assert_eq!(report.saved.len(), 4);
+
+    for n in [0, 50, 100, 150].iter() {
+        let att = id(2021, 3, 1, n * 10 + 1);
+        assert!(dir.path().join(format!("{}.png", att)).exists());
+    }
+
+    // The pins are requested once, without paging through the messages
+    let requests = mock.requests();
+    assert!(mock.pages().is_empty());
+    assert_eq!(
+        requests.iter().filter(|req| req.ends_with("/pins")).count(),
+        1
+    );
+}
+
+#[tokio::test]
+async fn uses_messages_as_anchors() {
+    let mock = Mock::start().await;
+
+    mock.add(CHANNEL, history(2021, 1, 1, 300));
+
+    let saved = |configure: fn(Downloader) -> Downloader| {
+        let dir = TempDir::new().unwrap();
+        let downloader = configure(downloader(&mock, &dir)).dry_run(true);
+
+        async move {
+            let report = downloader.run().await.unwrap();
+            let mut ids: Vec<u64> = report.matched.iter().map(|m| m.id).collect();
+            ids.sort_unstable();
+            ids
+        }
+    };
+
+    fn msg(n: u64) -> u64 {
+        id(2021, 1, 1, n * 10)
+    }
+
+    let att = |n: u64| msg(n) + 1;
+
+    // Everything after the 250th message
+    let after = saved(|d| d.after(msg(250))).await;
+    assert_eq!(after, (251..300).map(att).collect::<Vec<u64>>());
+
+    // Everything before the 120th, in both orders
+    let before = saved(|d| d.before(msg(120))).await;
+    assert_eq!(before, (0..120).map(att).collect::<Vec<u64>>());
+
+    let newest = saved(|d| d.before(msg(120)).order(Order::Newest)).await;
+    assert_eq!(newest, before);
+
+    // Both ends at once
+    let between = saved(|d| d.after(msg(10)).before(msg(20))).await;
+    assert_eq!(between, (11..20).map(att).collect::<Vec<u64>>());
+
+    // The page of messages around one, which includes it
+    let around = saved(|d| d.around(msg(150))).await;
+    assert_eq!(around.len(), 100);
+    assert!(around.contains(&att(150)));
+    assert!(mock
+        .requests()
+        .iter()
+        .any(|req| req.contains(&format!("around={}", msg(150)))));
+}
+
+#[tokio::test]
+async fn stops_at_the_limit_when_only_text_follows() {
+    let mock = Mock::start().await;
+    let dir = TempDir::new().unwrap();
+
+    mock.add(CHANNEL, history(2021, 1, 1, 3));
+    mock.add(
+        CHANNEL,
+        (0..400)
+            .map(|n| message(id(2021, 1, 2, n), CHANNEL, "designer", "gg", Vec::new()))
+            .collect(),
+    );
+
+    let report = downloader(&mock, &dir)
+        .since(Utc.ymd(2020, 12, 31).and_hms(0, 0, 0))
+        .limit(3)
+        .run()
+        .await
+        .unwrap();
+
+    assert_eq!(report.saved.len(), 3);
+    assert_eq!(mock.pages().len(), 1);
+}
+
+#[tokio::test]
+async fn keeps_the_collage_when_only_the_archive_is_kept() {
+    let mock = Mock::start().await;
+    let dir = TempDir::new().unwrap();
+    let folder = dir.path().join("images");
+
+    mock.add(CHANNEL, history(2021, 1, 1, 3));
+
+    let report = Downloader::new(TOKEN, &CHANNEL.to_string())
+        .endpoints(mock.endpoints())
+        .path(&folder)
+        .since(Utc.ymd(2020, 12, 31).and_hms(0, 0, 0))
+        .collage(collage::Options {
+            width: 64,
+            ..collage::Options::default()
+        })
+        .gallery(true)
+        .archive(archive::Options {
+            kind: archive::Kind::Zip,
+            keep_folder: false,
+        })
+        .run()
+        .await
+        .unwrap();
+
+    assert!(report.archive.as_ref().unwrap().exists());
+
+    // The images and the gallery linking to them are only in the archive,
+    // while the collage the report points to is still there
+    assert!(report.saved.iter().all(|file| !file.exists()));
+    assert_eq!(report.gallery, None);
+    assert!(!folder.join(gallery::FILE).exists());
+    assert!(report.collage.as_ref().unwrap().exists());
+}
+
+#[tokio::test]
+async fn strips_photos_before_converting_them() {
+    let mock = Mock::start().await;
+    let dir = TempDir::new().unwrap();
+
+    let msg = id(2021, 1, 1, 0);
+    mock.add(
+        CHANNEL,
+        vec![message(
+            msg,
+            CHANNEL,
+            "photographer",
+            "",
+            vec![image(msg + 1, CHANNEL, "beach.jpg")],
+        )],
+    );
+    mock.serve(msg + 1, photo());
+
+    let report = downloader(&mock, &dir)
+        .since(Utc.ymd(2020, 12, 31).and_hms(0, 0, 0))
+        .convert(convert::Options {
+            keep_original: true,
+            ..convert::Options::default()
+        })
+        .strip_metadata(true)
+        .run()
+        .await
+        .unwrap();
+
+    // The location is still found even though the PNG couldn't contain it
+    let converted = dir.path().join(format!("{}.png", msg + 1));
+    assert_eq!(report.saved, vec![converted.clone()]);
+    assert_eq!(report.located, vec![converted]);
+
+    // The kept original no longer points to the GPS directory either
+    let original = read(
+        dir.path()
+            .join(convert::FOLDER)
+            .join(format!("{}.jpg", msg + 1)),
+    )
+    .unwrap();
+    assert!(!original.windows(2).any(|tag| tag == [0x88, 0x25]));
+    assert!(image::load_from_memory(&original).is_ok());
+}
@@ -0,0 +1,121 @@
+mod mock;
+
+use {
+    chrono::{TimeZone, Utc},
+    discord_image_downloader::{
+        downloader::Downloader,
+        guilds::{self, Channel, Guild},
+    },
+    mock::{id, image, message, Mock, GUILD, TOKEN},
+    std::path::Path,
+    tempfile::TempDir,
+};
+
+const GENERAL: u64 = 850000000000000001;
+const VOICE: u64 = 850000000000000002;
+const SUCCESS: u64 = 850000000000000003;
+const THREAD: u64 = 850000000000000004;
+const COMMUNITY: u64 = 850000000000000005;
+const ANNOUNCEMENTS: u64 = 850000000000000006;
+const STAFF: u64 = 850000000000000007;
+
+fn channel(id: u64, name: &str, thread: bool) -> Channel {
+    Channel {
+        id: id.to_string(),
+        name: name.to_string(),
+        thread,
+    }
+}
+
+#[tokio::test]
+async fn lists_the_text_channels_and_threads() {
+    let mock = Mock::start().await;
+    let client = reqwest::Client::new();
+
+    mock.describe(GENERAL, "general", 0, Some(COMMUNITY));
+    mock.describe(VOICE, "Lounge", 2, None);
+    mock.describe(SUCCESS, "success", 5, Some(COMMUNITY));
+    mock.describe(THREAD, "restock-wins", 11, Some(GENERAL));
+    mock.describe(COMMUNITY, "Community", 4, None);
+    mock.describe(ANNOUNCEMENTS, "announcements", 5, None);
+    mock.describe(STAFF, "staff", 0, None);
+    mock.forbid(STAFF);
+
+    let servers = guilds::list(&client, &mock.endpoints(), TOKEN)
+        .await
+        .unwrap();
+    assert_eq!(
+        servers,
+        vec![Guild {
+            id: GUILD.to_string(),
+            name: String::from("Mock Server"),
+        }]
+    );
+
+    // Voice channels can't contain images, channels the bot can't read are
+    // left out, and threads follow their channel. Channels without a category
+    // come first, even when their own position is further down.
+    let channels = guilds::channels(&client, &mock.endpoints(), TOKEN, &servers[0].id)
+        .await
+        .unwrap();
+    assert_eq!(
+        channels,
+        vec![
+            channel(ANNOUNCEMENTS, "announcements", false),
+            channel(GENERAL, "general", false),
+            channel(THREAD, "restock-wins", true),
+            channel(SUCCESS, "success", false),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn downloads_each_picked_channel_into_its_own_folder() {
+    let mock = Mock::start().await;
+    let dir = TempDir::new().unwrap();
+
+    for (n, channel) in [GENERAL, SUCCESS].iter().enumerate() {
+        let msg = id(2021, 1, 1, n as u64 * 10);
+        mock.add(
+            *channel,
+            vec![message(
+                msg,
+                *channel,
+                "designer",
+                "",
+                vec![image(msg + 1, *channel, "a.png")],
+            )],
+        );
+    }
+
+    let mut selected = Downloader::new(TOKEN, &GENERAL.to_string())
+        .endpoints(mock.endpoints())
+        .path(dir.path())
+        .since(Utc.ymd(2020, 12, 31).and_hms(0, 0, 0))
+        .export(dir.path().join("images.csv"))
+        .config()
+        .clone();
+    selected.channels = vec![SUCCESS.to_string()];
+
+    let split = selected.split();
+    assert_eq!(split.len(), 2);
+
+    for (n, selected) in split.into_iter().enumerate() {
+        let channel = selected.channel.clone();
+        let folder = dir.path().join(&channel);
+        assert_eq!(Path::new(&selected.path), folder);
+
+        // Each channel's list is exported next to the others instead of
+        // replacing them
+        let export = dir.path().join(format!("images-{}.csv", channel));
+        assert_eq!(selected.export.as_ref(), Some(&export));
+
+        let report = Downloader::from(selected).run().await.unwrap();
+        assert_eq!(report.channel, channel);
+        assert_eq!(
+            report.saved,
+            vec![folder.join(format!("{}.png", id(2021, 1, 1, n as u64 * 10 + 1)))]
+        );
+        assert!(export.exists());
+    }
+}
@@ -0,0 +1,214 @@
+use {
+    discord_image_downloader::metadata::strip,
+    image::GenericImageView,
+    std::fs::{read, write},
+    tempfile::TempDir,
+};
+
+// A big-endian TIFF with the given orientation, and a GPS directory with a
+// latitude if `gps` is set
+fn tiff(orientation: Option<u16>, gps: bool) -> Vec<u8> {
+    let mut entries: Vec<(u16, u16, u32, u32)> = Vec::new();
+
+    if let Some(orientation) = orientation {
+        entries.push((0x0112, 3, 1, (orientation as u32) << 16));
+    }
+
+    let gps_offset = 8 + 2 + 12 * (entries.len() as u32 + gps as u32) + 4;
+
+    if gps {
+        entries.push((0x8825, 4, 1, gps_offset));
+    }
+
+    let mut tiff = b"MM\0\x2A".to_vec();
+    tiff.extend_from_slice(&8u32.to_be_bytes());
+
+    let mut directory = |entries: &[(u16, u16, u32, u32)]| {
+        tiff.extend_from_slice(&(entries.len() as u16).to_be_bytes());
+        for (tag, kind, count, value) in entries {
+            tiff.extend_from_slice(&tag.to_be_bytes());
+            tiff.extend_from_slice(&kind.to_be_bytes());
+            tiff.extend_from_slice(&count.to_be_bytes());
+            tiff.extend_from_slice(&value.to_be_bytes());
+        }
+        tiff.extend_from_slice(&0u32.to_be_bytes());
+    };
+
+    directory(&entries);
+
+    if gps {
+        directory(&[(0x0002, 5, 3, 0)]);
+    }
+
+    tiff
+}
+
+fn segment(marker: u8, body: &[u8]) -> Vec<u8> {
+    let mut segment = vec![0xFF, marker];
+    segment.extend_from_slice(&(body.len() as u16 + 2).to_be_bytes());
+    segment.extend_from_slice(body);
+    segment
+}
+
+// The pixels don't matter, as only the segments before them are changed
+fn jpeg(exif: Option<Vec<u8>>) -> Vec<u8> {
+    let mut data = vec![0xFF, 0xD8];
+    data.extend(segment(0xE0, b"JFIF\0\x01\x01\0\0\x01\0\x01\0\0"));
+
+    if let Some(tiff) = exif {
+        data.extend(segment(0xE1, &[b"Exif\0\0".as_ref(), &tiff].concat()));
+    }
+
+    data.extend(segment(0xFE, b"taken at home"));
+    data.extend(segment(0xDA, b"\x01\x01\0\0\x3F\0"));
+    data.extend_from_slice(&[0x12, 0x34, 0xFF, 0xD9]);
+    data
+}
+
+fn chunk(kind: &[u8], body: &[u8]) -> Vec<u8> {
+    let mut chunk = kind.to_vec();
+    chunk.extend_from_slice(&(body.len() as u32).to_le_bytes());
+    chunk.extend_from_slice(body);
+    if body.len() % 2 == 1 {
+        chunk.push(0);
+    }
+    chunk
+}
+
+fn webp(exif: Vec<u8>) -> Vec<u8> {
+    // The ICC, EXIF and XMP flags are all set, along with the alpha one
+    let chunks = [
+        chunk(
+            b"VP8X",
+            &[0x20 | 0x10 | 0x08 | 0x04, 0, 0, 0, 3, 0, 0, 3, 0, 0],
+        ),
+        chunk(b"ICCP", b"profile"),
+        chunk(b"VP8L", b"\x2F\0\0\0\0"),
+        chunk(b"EXIF", &exif),
+        chunk(b"XMP ", b"<x:xmpmeta/>"),
+    ]
+    .concat();
+
+    let mut data = b"RIFF".to_vec();
+    data.extend_from_slice(&(chunks.len() as u32 + 4).to_le_bytes());
+    data.extend_from_slice(b"WEBP");
+    data.extend(chunks);
+    data
+}
+
+fn contains(data: &[u8], needle: &[u8]) -> bool {
+    data.windows(needle.len()).any(|window| window == needle)
+}
+
+fn stripped(name: &str, data: &[u8]) -> (Vec<u8>, bool, bool) {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join(name);
+    write(&path, data).unwrap();
+
+    let report = strip(&path).unwrap();
+    (read(&path).unwrap(), report.location, report.stripped)
+}
+
+#[test]
+fn removes_the_location_but_keeps_the_orientation() {
+    let (data, location, changed) = stripped("photo.jpg", &jpeg(Some(tiff(Some(6), true))));
+
+    assert!(location && changed);
+    assert!(!contains(&data, b"taken at home"));
+    assert!(!contains(&data, &0x8825u16.to_be_bytes()));
+
+    // Only the orientation is left in the EXIF block
+    let exif = [b"Exif\0\0".as_ref(), &tiff(Some(6), false)].concat();
+    assert!(contains(&data, &exif));
+    assert!(data.ends_with(&[0x12, 0x34, 0xFF, 0xD9]));
+}
+
+#[test]
+fn drops_exif_without_a_location_or_rotation() {
+    let (data, location, changed) = stripped("photo.jpg", &jpeg(Some(tiff(Some(1), false))));
+
+    assert!(!location && changed);
+    assert!(!contains(&data, b"Exif"));
+    assert_eq!(data, {
+        let mut expected = jpeg(None);
+        let comment = segment(0xFE, b"taken at home");
+        let start = expected
+            .windows(comment.len())
+            .position(|window| window == comment.as_slice())
+            .unwrap();
+        expected.drain(start..start + comment.len());
+        expected
+    });
+}
+
+#[test]
+fn clears_the_webp_flags() {
+    let (data, location, changed) = stripped("photo.webp", &webp(tiff(None, true)));
+
+    assert!(location && changed);
+    assert!(!contains(&data, b"ICCP") && !contains(&data, b"EXIF") && !contains(&data, b"XMP "));
+
+    // Only the alpha flag is left, and the RIFF size matches the new length
+    assert_eq!(data[20], 0x10);
+    assert_eq!(
+        u32::from_le_bytes([data[4], data[5], data[6], data[7]]) as usize,
+        data.len() - 8
+    );
+
+    // Keeping the orientation keeps its flag too
+    let (data, _, _) = stripped("photo.webp", &webp(tiff(Some(8), false)));
+    assert_eq!(data[20], 0x10 | 0x08);
+    assert!(contains(&data, b"EXIF"));
+}
+
+#[test]
+fn leaves_broken_files_untouched() {
+    let mut truncated = jpeg(Some(tiff(None, true)));
+    truncated.truncate(30);
+
+    // A segment that claims to be longer than the file
+    let mut garbage = jpeg(None);
+    garbage[4] = 0xFF;
+
+    let mut short_webp = webp(tiff(None, true));
+    short_webp.truncate(40);
+
+    for (name, data) in [
+        ("photo.jpg", truncated),
+        ("other.jpg", garbage),
+        ("photo.webp", short_webp),
+    ]
+    .iter()
+    {
+        let (after, _, changed) = stripped(name, data);
+        assert!(!changed, "{}", name);
+        assert_eq!(&after, data);
+    }
+}
+
+#[test]
+fn rewrites_png_exif_with_a_valid_checksum() {
+    let mut png = Vec::new();
+    image::DynamicImage::ImageRgb8(image::RgbImage::new(2, 3))
+        .write_to(&mut png, image::ImageOutputFormat::Png)
+        .unwrap();
+
+    // The `eXIf` chunk goes right after `IHDR`, which is 25 bytes long, and
+    // its checksum doesn't matter since it's removed
+    let body = tiff(Some(6), true);
+    let mut chunk = (body.len() as u32).to_be_bytes().to_vec();
+    chunk.extend_from_slice(b"eXIf");
+    chunk.extend_from_slice(&body);
+    chunk.extend_from_slice(&[0; 4]);
+    png.splice(33..33, chunk);
+
+    let (data, location, changed) = stripped("photo.png", &png);
+
+    assert!(location && changed);
+    assert!(contains(&data, &tiff(Some(6), false)));
+    assert!(!contains(&data, &0x8825u16.to_be_bytes()));
+
+    // The decoder checks every chunk's checksum
+    let img = image::load_from_memory(&data).unwrap();
+    assert_eq!((img.width(), img.height()), (2, 3));
+}
@@ -0,0 +1,501 @@
+// Not every test file uses every part of the mock
+#![allow(dead_code)]
+
+use {
+    chrono::{TimeZone, Utc},
+    discord_image_downloader::endpoint::Endpoints,
+    hyper::{
+        body::to_bytes,
+        service::{make_service_fn, service_fn},
+        Body, Method, Request, Response, Server, StatusCode,
+    },
+    serde_json::{json, Value},
+    std::{
+        collections::{HashMap, HashSet},
+        convert::Infallible,
+        net::SocketAddr,
+        sync::{Arc, Mutex},
+    },
+};
+
+pub const TOKEN: &str = "mock-token";
+pub const GUILD: u64 = 800000000000000000;
+
+// This is a tiny stand-in for Discord, serving the parts of the API the
+// program uses (`gateway/bot`, channels, their messages and pins) from
+// fixtures, along with a CDN that returns the same small PNG for every
+// attachment unless it was told otherwise. Requests are logged so that tests can check what was asked for.
+pub struct Mock {
+    pub base: String,
+    state: Arc<Mutex<State>>,
+}
+
+#[derive(Default)]
+struct State {
+    channels: HashMap<u64, Channel>,
+    // Attachments whose download fails, to test how errors are handled
+    broken: HashSet<u64>,
+    // Attachments served as something other than the usual PNG
+    served: HashMap<u64, Vec<u8>>,
+    requests: Vec<String>,
+    user_agents: Vec<String>,
+    // The channel and body of every message posted through the API
+    posted: Vec<(u64, Vec<u8>)>,
+}
+
+#[derive(Default)]
+struct Channel {
+    // Messages are kept sorted by ID, from oldest to newest
+    messages: Vec<(u64, Value)>,
+    forbidden: bool,
+    // How the channel is listed in the server, set with `Mock::describe()`
+    name: Option<String>,
+    kind: u64,
+    parent: Option<u64>,
+}
+
+impl Mock {
+    pub async fn start() -> Mock {
+        let state = Arc::new(Mutex::new(State::default()));
+        let shared = state.clone();
+
+        let make = make_service_fn(move |_| {
+            let state = shared.clone();
+
+            async move {
+                Ok::<_, Infallible>(service_fn(move |req| {
+                    let state = state.clone();
+                    async move { Ok::<_, Infallible>(handle(state, req).await) }
+                }))
+            }
+        });
+
+        let server = Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0))).serve(make);
+        let base = format!("http://{}", server.local_addr());
+
+        tokio::spawn(server);
+
+        Mock { base, state }
+    }
+
+    pub fn endpoints(&self) -> Endpoints {
+        Endpoints {
+            api: format!("{}/api/v10", self.base),
+            cdn: self.base.clone(),
+        }
+    }
+
+    // Adds the messages to the channel, creating it if it doesn't exist yet
+    pub fn add(&self, channel: u64, messages: Vec<Value>) {
+        let mut state = self.state.lock().unwrap();
+        let entry = state.channels.entry(channel).or_default();
+
+        for msg in messages {
+            let id = msg["id"].as_str().unwrap().parse().unwrap();
+            entry.messages.push((id, msg));
+        }
+
+        entry.messages.sort_by_key(|(id, _)| *id);
+    }
+
+    // Every channel is in the mock's only server, called `Mock Server`, and
+    // is listed with this name and type, along with the channel it was
+    // started in if it's a thread
+    pub fn describe(&self, channel: u64, name: &str, kind: u64, parent: Option<u64>) {
+        let mut state = self.state.lock().unwrap();
+        let entry = state.channels.entry(channel).or_default();
+        entry.name = Some(name.to_string());
+        entry.kind = kind;
+        entry.parent = parent;
+    }
+
+    // The bot will get `Missing Access` errors for this channel
+    pub fn forbid(&self, channel: u64) {
+        let mut state = self.state.lock().unwrap();
+        state.channels.entry(channel).or_default().forbidden = true;
+    }
+
+    pub fn break_attachment(&self, id: u64) {
+        self.state.lock().unwrap().broken.insert(id);
+    }
+
+    // The attachment is downloaded as these bytes instead of `png()`
+    pub fn serve(&self, id: u64, bytes: Vec<u8>) {
+        self.state.lock().unwrap().served.insert(id, bytes);
+    }
+
+    pub fn requests(&self) -> Vec<String> {
+        self.state.lock().unwrap().requests.clone()
+    }
+
+    pub fn user_agents(&self) -> Vec<String> {
+        self.state.lock().unwrap().user_agents.clone()
+    }
+
+    // Only requests for pages of messages, in the order they were made
+    pub fn pages(&self) -> Vec<String> {
+        self.requests()
+            .into_iter()
+            .filter(|req| req.starts_with("GET /api/v10/channels/") && req.contains("/messages"))
+            .collect()
+    }
+
+    pub fn posted(&self) -> Vec<(u64, Vec<u8>)> {
+        self.state.lock().unwrap().posted.clone()
+    }
+}
+
+// Snowflakes start with the milliseconds since the Discord Epoch, so IDs made
+// from a date sort the same way the dates do, with `n` telling apart messages
+// sent in the same millisecond
+pub fn id(year: i32, month: u32, day: u32, n: u64) -> u64 {
+    discord_image_downloader::config::snowflake(Utc.ymd(year, month, day).and_hms(12, 0, 0)) + n
+}
+
+pub fn message(
+    id: u64,
+    channel: u64,
+    author: &str,
+    content: &str,
+    attachments: Vec<Value>,
+) -> Value {
+    let millis = (id >> 22) as i64 + 1420070400000;
+    let author_id = 100000000000000000 + author.bytes().map(|b| b as u64).sum::<u64>();
+
+    json!({
+        "id": id.to_string(),
+        "channel_id": channel.to_string(),
+        "author": {
+            "id": author_id.to_string(),
+            "username": author,
+            "discriminator": "0",
+            "avatar": null
+        },
+        "content": content,
+        "timestamp": Utc.timestamp_millis(millis).to_rfc3339(),
+        "edited_timestamp": null,
+        "tts": false,
+        "mention_everyone": false,
+        "mentions": [],
+        "mention_roles": [],
+        "attachments": attachments,
+        "embeds": [],
+        "pinned": false,
+        "type": 0
+    })
+}
+
+// Adds reactions, made with `emoji()` or `custom_emoji()`, to the message
+pub fn react(mut msg: Value, reactions: Vec<Value>) -> Value {
+    msg["reactions"] = Value::Array(reactions);
+    msg
+}
+
+pub fn emoji(name: &str, count: u64) -> Value {
+    json!({
+        "count": count,
+        "me": false,
+        "emoji": { "id": null, "name": name }
+    })
+}
+
+pub fn custom_emoji(id: u64, name: &str, count: u64) -> Value {
+    json!({
+        "count": count,
+        "me": false,
+        "emoji": { "id": id.to_string(), "name": name, "animated": false }
+    })
+}
+
+// Attachments with a width and height are images
+pub fn image(id: u64, channel: u64, filename: &str) -> Value {
+    let mut att = file(id, channel, filename);
+    att["width"] = json!(4);
+    att["height"] = json!(4);
+    att["content_type"] = json!("image/png");
+    att
+}
+
+pub fn file(id: u64, channel: u64, filename: &str) -> Value {
+    // Like Discord's current ones, the links are signed and expire, which
+    // adds a query string after the file name
+    let url = format!(
+        "https://cdn.discordapp.com/attachments/{}/{}/{}?ex=65f1c2a0&is=65df4da0&hm=3f9c2b7e1d&",
+        channel, id, filename
+    );
+
+    json!({
+        "id": id.to_string(),
+        "filename": filename,
+        "size": png().len(),
+        "url": url,
+        "proxy_url": url.replace("cdn.discordapp.com", "media.discordapp.net")
+    })
+}
+
+// Every attachment is served as this 4x4 PNG
+pub fn png() -> Vec<u8> {
+    let img = image::RgbImage::from_pixel(4, 4, image::Rgb([200, 30, 30]));
+    let mut bytes = Vec::new();
+
+    image::DynamicImage::ImageRgb8(img)
+        .write_to(&mut bytes, image::ImageOutputFormat::Png)
+        .unwrap();
+
+    bytes
+}
+
+// A 4x4 JPEG taken by a phone, with the coordinates of where it was taken in
+// its EXIF data
+pub fn photo() -> Vec<u8> {
+    let img = image::RgbImage::from_pixel(4, 4, image::Rgb([30, 200, 30]));
+    let mut bytes = Vec::new();
+
+    image::DynamicImage::ImageRgb8(img)
+        .write_to(&mut bytes, image::ImageOutputFormat::Jpeg(90))
+        .unwrap();
+
+    // A big-endian TIFF whose only entry points to a GPS directory with a
+    // latitude, added in an EXIF segment right after the start of the image
+    let tiff: &[u8] = &[
+        b'M', b'M', 0, 0x2A, 0, 0, 0, 8, //
+        0, 1, 0x88, 0x25, 0, 4, 0, 0, 0, 1, 0, 0, 0, 26, 0, 0, 0, 0, //
+        0, 1, 0, 2, 0, 5, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 0,
+    ];
+    let body = [b"Exif\0\0".as_ref(), tiff].concat();
+
+    let mut segment = vec![0xFF, 0xE1];
+    segment.extend_from_slice(&(body.len() as u16 + 2).to_be_bytes());
+    segment.extend_from_slice(&body);
+    bytes.splice(2..2, segment);
+
+    bytes
+}
+
+fn reply(status: StatusCode, body: Value) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .header("Content-Type", "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
+
+fn error(status: StatusCode, code: u64, message: &str) -> Response<Body> {
+    reply(status, json!({ "code": code, "message": message }))
+}
+
+async fn handle(state: Arc<Mutex<State>>, req: Request<Body>) -> Response<Body> {
+    let method = req.method().clone();
+    let path = req.uri().path().to_string();
+    let query: HashMap<String, String> = req
+        .uri()
+        .query()
+        .unwrap_or("")
+        .split('&')
+        .filter_map(|pair| {
+            let mut parts = pair.splitn(2, '=');
+            Some((parts.next()?.to_string(), parts.next()?.to_string()))
+        })
+        .collect();
+
+    let user_agent = req
+        .headers()
+        .get("User-Agent")
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or("")
+        .to_string();
+    state.lock().unwrap().user_agents.push(user_agent);
+
+    state.lock().unwrap().requests.push(format!(
+        "{} {}{}",
+        method,
+        path,
+        req.uri()
+            .query()
+            .map(|q| format!("?{}", q))
+            .unwrap_or_default()
+    ));
+
+    let parts: Vec<&str> = path.trim_start_matches('/').split('/').collect();
+
+    // The CDN doesn't need authorization
+    if parts[0] == "attachments" {
+        let id: u64 = parts.get(2).and_then(|id| id.parse().ok()).unwrap_or(0);
+
+        if state.lock().unwrap().broken.contains(&id) {
+            return Response::builder()
+                .status(StatusCode::NOT_FOUND)
+                .body(Body::from("Not Found"))
+                .unwrap();
+        }
+
+        let bytes = state.lock().unwrap().served.get(&id).cloned();
+
+        return Response::builder()
+            .header("Content-Type", "image/png")
+            .body(Body::from(bytes.unwrap_or_else(png)))
+            .unwrap();
+    }
+
+    if parts.len() < 3 || parts[0] != "api" || parts[1] != "v10" {
+        return error(StatusCode::NOT_FOUND, 0, "404: Not Found");
+    }
+
+    let authorized = req
+        .headers()
+        .get("Authorization")
+        .and_then(|value| value.to_str().ok())
+        == Some(format!("Bot {}", TOKEN).as_str());
+
+    if !authorized {
+        return error(StatusCode::UNAUTHORIZED, 0, "401: Unauthorized");
+    }
+
+    match (&method, &parts[2..]) {
+        (&Method::GET, ["gateway", "bot"]) => reply(
+            StatusCode::OK,
+            json!({
+                "url": "wss://gateway.discord.gg",
+                "shards": 1,
+                "session_start_limit": {
+                    "total": 1000,
+                    "remaining": 1000,
+                    "reset_after": 0,
+                    "max_concurrency": 1
+                }
+            }),
+        ),
+        (&Method::GET, ["users", "@me", "guilds"]) => reply(
+            StatusCode::OK,
+            json!([{ "id": GUILD.to_string(), "name": "Mock Server" }]),
+        ),
+        (&Method::GET, ["guilds", guild, rest @ ..]) if *guild == GUILD.to_string() => {
+            let state = state.lock().unwrap();
+
+            // Channels are positioned in the order of their IDs
+            let mut listed: Vec<(&u64, &Channel)> = state.channels.iter().collect();
+            listed.sort_by_key(|(id, _)| **id);
+
+            let listed = listed
+                .into_iter()
+                .enumerate()
+                .map(|(position, (id, channel))| {
+                    json!({
+                        "id": id.to_string(),
+                        "type": channel.kind,
+                        "guild_id": GUILD.to_string(),
+                        "name": channel.name.clone().unwrap_or_else(|| String::from("success")),
+                        "position": position,
+                        "parent_id": channel.parent.map(|parent| parent.to_string())
+                    })
+                });
+
+            // Threads are only returned by the active threads endpoint
+            let (threads, channels): (Vec<Value>, Vec<Value>) =
+                listed.partition(|channel| channel["type"].as_u64().unwrap_or(0) >= 10);
+
+            match rest {
+                ["channels"] => reply(StatusCode::OK, Value::Array(channels)),
+                ["threads", "active"] => {
+                    reply(StatusCode::OK, json!({ "threads": threads, "members": [] }))
+                }
+                _ => error(StatusCode::NOT_FOUND, 0, "404: Not Found"),
+            }
+        }
+        (&Method::GET, ["guilds", ..]) => error(StatusCode::NOT_FOUND, 10004, "Unknown Guild"),
+        (_, ["channels", channel, rest @ ..]) => {
+            let channel: u64 = match channel.parse() {
+                Ok(channel) => channel,
+                Err(_) => return error(StatusCode::NOT_FOUND, 10003, "Unknown Channel"),
+            };
+
+            let body = to_bytes(req.into_body()).await.unwrap_or_default().to_vec();
+            let mut state = state.lock().unwrap();
+
+            let found = match state.channels.get(&channel) {
+                Some(found) => found,
+                None => return error(StatusCode::NOT_FOUND, 10003, "Unknown Channel"),
+            };
+
+            if found.forbidden {
+                return error(StatusCode::FORBIDDEN, 50001, "Missing Access");
+            }
+
+            match (&method, rest) {
+                (&Method::GET, []) => reply(
+                    StatusCode::OK,
+                    json!({
+                        "id": channel.to_string(),
+                        "type": found.kind,
+                        "guild_id": GUILD.to_string(),
+                        "name": found.name.clone().unwrap_or_else(|| String::from("success"))
+                    }),
+                ),
+                (&Method::GET, ["messages"]) => {
+                    reply(StatusCode::OK, Value::Array(page(&found.messages, &query)))
+                }
+                (&Method::GET, ["pins"]) => reply(
+                    StatusCode::OK,
+                    Value::Array(
+                        found
+                            .messages
+                            .iter()
+                            .rev()
+                            .filter(|(_, msg)| msg["pinned"] == json!(true))
+                            .map(|(_, msg)| msg.clone())
+                            .collect(),
+                    ),
+                ),
+                (&Method::POST, ["messages"]) => {
+                    state.posted.push((channel, body));
+                    reply(
+                        StatusCode::OK,
+                        message(id(2021, 6, 1, 999), channel, "bot", "", Vec::new()),
+                    )
+                }
+                _ => error(StatusCode::NOT_FOUND, 0, "404: Not Found"),
+            }
+        }
+        _ => error(StatusCode::NOT_FOUND, 0, "404: Not Found"),
+    }
+}
+
+// This follows Discord's rules: pages hold up to `limit` messages (50 by
+// default, 100 at most), always sorted from newest to oldest, and are the ones
+// closest to the `before`, `after` or `around` message, or the newest ones
+fn page(messages: &[(u64, Value)], query: &HashMap<String, String>) -> Vec<Value> {
+    let limit = query
+        .get("limit")
+        .and_then(|limit| limit.parse().ok())
+        .unwrap_or(50usize)
+        .clamp(1, 100);
+    let cursor = |name: &str| query.get(name).and_then(|id| id.parse::<u64>().ok());
+
+    let selected: Vec<&(u64, Value)> = if let Some(before) = cursor("before") {
+        let older: Vec<_> = messages.iter().filter(|(id, _)| *id < before).collect();
+        older[older.len().saturating_sub(limit)..].to_vec()
+    } else if let Some(after) = cursor("after") {
+        messages
+            .iter()
+            .filter(|(id, _)| *id > after)
+            .take(limit)
+            .collect()
+    } else if let Some(around) = cursor("around") {
+        let index = messages
+            .iter()
+            .position(|(id, _)| *id >= around)
+            .unwrap_or(messages.len());
+        let start = index.saturating_sub(limit / 2);
+        messages.iter().skip(start).take(limit).collect()
+    } else {
+        messages[messages.len().saturating_sub(limit)..]
+            .iter()
+            .collect()
+    };
+
+    selected
+        .into_iter()
+        .rev()
+        .map(|(_, msg)| msg.clone())
+        .collect()
+}
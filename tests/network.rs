@@ -0,0 +1,116 @@
+mod mock;
+
+use {
+    chrono::{TimeZone, Utc},
+    discord_image_downloader::{
+        downloader::Downloader,
+        endpoint::Endpoints,
+        error::Error,
+        http::{self, Options, DEFAULT_USER_AGENT},
+    },
+    mock::{id, image, message, Mock, TOKEN},
+    std::fs::write,
+    tempfile::TempDir,
+};
+
+const CHANNEL: u64 = 850000000000000002;
+
+fn fixtures(mock: &Mock) {
+    mock.add(
+        CHANNEL,
+        (0..3)
+            .map(|n| {
+                let msg = id(2021, 1, 1, n * 10);
+                message(
+                    msg,
+                    CHANNEL,
+                    "designer",
+                    "",
+                    vec![image(msg + 1, CHANNEL, "success.png")],
+                )
+            })
+            .collect(),
+    );
+}
+
+#[tokio::test]
+async fn sends_the_configured_user_agent() {
+    let mock = Mock::start().await;
+    let dir = TempDir::new().unwrap();
+
+    fixtures(&mock);
+
+    let client = http::client(&Options {
+        user_agent: Some(String::from("SuccessArchiver/1.0")),
+        ..Options::default()
+    })
+    .unwrap();
+
+    Downloader::new(TOKEN, &CHANNEL.to_string())
+        .endpoints(mock.endpoints())
+        .client(client)
+        .path(dir.path())
+        .run()
+        .await
+        .unwrap();
+
+    let agents = mock.user_agents();
+    assert!(!agents.is_empty());
+    assert!(agents.iter().all(|agent| agent == "SuccessArchiver/1.0"));
+
+    // Without one, the bot identifies itself the way Discord asks it to
+    assert!(DEFAULT_USER_AGENT.starts_with("DiscordBot ("));
+}
+
+#[tokio::test]
+async fn sends_every_request_through_the_proxy() {
+    let mock = Mock::start().await;
+    let dir = TempDir::new().unwrap();
+
+    fixtures(&mock);
+
+    // The hosts don't exist, so the download only works if the requests go
+    // through the mock, acting as the proxy
+    let client = http::client(&Options {
+        proxy: Some(mock.base.clone()),
+        ..Options::default()
+    })
+    .unwrap();
+
+    let report = Downloader::new(TOKEN, &CHANNEL.to_string())
+        .endpoints(Endpoints {
+            api: String::from("http://discord.invalid/api/v10"),
+            cdn: String::from("http://cdn.invalid"),
+        })
+        .client(client)
+        .path(dir.path())
+        .since(Utc.ymd(2020, 12, 31).and_hms(0, 0, 0))
+        .run()
+        .await
+        .unwrap();
+
+    assert_eq!(report.saved.len(), 3);
+    assert!(report.errors.is_empty());
+}
+
+#[test]
+fn rejects_invalid_settings() {
+    let dir = TempDir::new().unwrap();
+    let bundle = dir.path().join("bundle.pem");
+    write(&bundle, "not a certificate").unwrap();
+
+    for options in [
+        Options {
+            proxy: Some(String::from("not a proxy")),
+            ..Options::default()
+        },
+        Options {
+            ca_bundle: Some(bundle),
+            ..Options::default()
+        },
+    ]
+    .iter()
+    {
+        assert!(matches!(http::client(options), Err(Error::Parse(_))));
+    }
+}